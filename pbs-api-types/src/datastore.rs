@@ -14,8 +14,8 @@ use crate::{
     Authid, CryptMode, Fingerprint, GroupFilter, MaintenanceMode, MaintenanceType, Userid,
     BACKUP_ID_RE, BACKUP_NS_RE, BACKUP_TIME_RE, BACKUP_TYPE_RE, DATASTORE_NOTIFY_STRING_SCHEMA,
     GC_SCHEDULE_SCHEMA, GROUP_OR_SNAPSHOT_PATH_REGEX_STR, PROXMOX_SAFE_ID_FORMAT,
-    PROXMOX_SAFE_ID_REGEX_STR, PRUNE_SCHEDULE_SCHEMA, SHA256_HEX_REGEX, SINGLE_LINE_COMMENT_SCHEMA,
-    SNAPSHOT_PATH_REGEX_STR, UPID,
+    PROXMOX_SAFE_ID_REGEX_STR, PRUNE_SCHEDULE_SCHEMA, SCHEDULE_RANDOMIZED_DELAY_SCHEMA,
+    SHA256_HEX_REGEX, SINGLE_LINE_COMMENT_SCHEMA, SNAPSHOT_PATH_REGEX_STR, UPID,
 };
 
 const_regex! {
@@ -145,6 +145,13 @@ pub const PRUNE_SCHEMA_KEEP_LAST: Schema = IntegerSchema::new("Number of backups
     .minimum(1)
     .schema();
 
+pub const PRUNE_SCHEMA_KEEP_LAST_VERIFIED: Schema = IntegerSchema::new(
+    "Number of most recent successfully verified backups to keep, even if other options would \
+    remove them.",
+)
+.minimum(1)
+.schema();
+
 pub const PRUNE_SCHEMA_KEEP_MONTHLY: Schema =
     IntegerSchema::new("Number of monthly backups to keep.")
         .minimum(1)
@@ -203,12 +210,23 @@ pub enum DatastoreFSyncLevel {
     Filesystem,
 }
 
+pub const DATASTORE_MAX_BACKUP_TIME_SKEW_SCHEMA: Schema = IntegerSchema::new(
+    "Maximum number of seconds the time of a new backup may lie ahead of the server time.",
+)
+.minimum(0)
+.default(300)
+.schema();
+
 #[api(
     properties: {
         "chunk-order": {
             type: ChunkOrder,
             optional: true,
         },
+        "max-backup-time-skew": {
+            schema: DATASTORE_MAX_BACKUP_TIME_SKEW_SCHEMA,
+            optional: true,
+        },
     },
 )]
 #[derive(Serialize, Deserialize, Default)]
@@ -220,6 +238,8 @@ pub struct DatastoreTuning {
     pub chunk_order: Option<ChunkOrder>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sync_level: Option<DatastoreFSyncLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_backup_time_skew: Option<i64>,
 }
 
 pub const DATASTORE_TUNING_STRING_SCHEMA: Schema = StringSchema::new("Datastore tuning options")
@@ -236,6 +256,10 @@ pub const DATASTORE_TUNING_STRING_SCHEMA: Schema = StringSchema::new("Datastore
         path: {
             schema: DIR_NAME_SCHEMA,
         },
+        "chunk-path": {
+            optional: true,
+            schema: DIR_NAME_SCHEMA,
+        },
         "notify-user": {
             optional: true,
             type: Userid,
@@ -252,6 +276,10 @@ pub const DATASTORE_TUNING_STRING_SCHEMA: Schema = StringSchema::new("Datastore
             optional: true,
             schema: GC_SCHEDULE_SCHEMA,
         },
+        "gc-randomized-delay": {
+            optional: true,
+            schema: SCHEDULE_RANDOMIZED_DELAY_SCHEMA,
+        },
         "prune-schedule": {
             optional: true,
             schema: PRUNE_SCHEDULE_SCHEMA,
@@ -285,12 +313,21 @@ pub struct DataStoreConfig {
     #[updater(skip)]
     pub path: String,
 
+    /// Separate location for the chunk directory, defaults to `path`
+    #[updater(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_path: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gc_schedule: Option<String>,
 
+    /// Maximum random delay added to the garbage collection schedule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gc_randomized_delay: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prune_schedule: Option<String>,
 
@@ -344,8 +381,10 @@ impl DataStoreConfig {
         Self {
             name,
             path,
+            chunk_path: None,
             comment: None,
             gc_schedule: None,
+            gc_randomized_delay: None,
             prune_schedule: None,
             keep: Default::default(),
             verify_new: None,
@@ -357,6 +396,11 @@ impl DataStoreConfig {
         }
     }
 
+    /// Returns the directory below which the chunk store's `.chunks` directory lives.
+    pub fn chunk_base_path(&self) -> &str {
+        self.chunk_path.as_deref().unwrap_or(&self.path)
+    }
+
     pub fn get_maintenance_mode(&self) -> Option<MaintenanceMode> {
         self.maintenance_mode.as_ref().and_then(|str| {
             MaintenanceMode::deserialize(proxmox_schema::de::SchemaDeserializer::new(
@@ -461,6 +505,18 @@ pub enum VerifyState {
     Failed,
 }
 
+#[api()]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// What a verify operation checks.
+pub enum VerifyMode {
+    /// Load and decode all chunks and check their digests
+    #[default]
+    Full,
+    /// Only check that all referenced chunks exist, without reading them
+    Presence,
+}
+
 #[api(
     properties: {
         upid: {
@@ -469,6 +525,11 @@ pub enum VerifyState {
         state: {
             type: VerifyState,
         },
+        aborted: {
+            type: bool,
+            optional: true,
+            default: false,
+        },
     },
 )]
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
@@ -478,6 +539,14 @@ pub struct SnapshotVerifyState {
     pub upid: UPID,
     /// State of the verification. Enum.
     pub state: VerifyState,
+    /// The verification got aborted before finding any error. The state is 'failed' then, so
+    /// older versions, which do not know about this flag, treat the snapshot as failed.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub aborted: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
 }
 
 /// A namespace provides a logical separation between backup groups from different domains
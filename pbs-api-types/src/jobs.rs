@@ -5,6 +5,7 @@ use const_format::concatcp;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
+use proxmox_human_byte::HumanByte;
 use proxmox_schema::*;
 
 use crate::{
@@ -58,6 +59,13 @@ pub const VERIFICATION_SCHEDULE_SCHEMA: Schema =
         .type_text("<calendar-event>")
         .schema();
 
+pub const SCHEDULE_RANDOMIZED_DELAY_SCHEMA: Schema = StringSchema::new(
+    "Delay the scheduled start by a random, but per job stable, amount of time up to this value.",
+)
+.format(&ApiStringFormat::VerifyFn(proxmox_time::verify_time_span))
+.type_text("<time-span>")
+.schema();
+
 pub const REMOVE_VANISHED_BACKUPS_SCHEMA: Schema = BooleanSchema::new(
     "Delete vanished backups. This remove the local copy if the remote backup was deleted.",
 )
@@ -171,6 +179,21 @@ pub const VERIFICATION_OUTDATED_AFTER_SCHEMA: Schema =
         .minimum(0)
         .schema();
 
+pub const VERIFICATION_WORKER_THREADS_SCHEMA: Schema = IntegerSchema::new(
+    "Number of threads used to decode and verify chunks. Defaults to the number of available \
+    CPUs, capped at 16.",
+)
+.minimum(1)
+.maximum(64)
+.schema();
+
+pub const VERIFICATION_CHECKPOINT_MAX_AGE_SCHEMA: Schema = IntegerSchema::new(
+    "Persist verified chunks, so that an interrupted job can resume. Checkpoints older than \
+    this number of hours are ignored.",
+)
+.minimum(1)
+.schema();
+
 #[api(
     properties: {
         id: {
@@ -203,6 +226,22 @@ pub const VERIFICATION_OUTDATED_AFTER_SCHEMA: Schema =
             optional: true,
             schema: crate::NS_MAX_DEPTH_SCHEMA,
         },
+        "worker-threads": {
+            optional: true,
+            schema: VERIFICATION_WORKER_THREADS_SCHEMA,
+        },
+        "randomized-delay": {
+            optional: true,
+            schema: SCHEDULE_RANDOMIZED_DELAY_SCHEMA,
+        },
+        "read-limit": {
+            optional: true,
+            type: HumanByte,
+        },
+        "checkpoint-max-age": {
+            optional: true,
+            schema: VERIFICATION_CHECKPOINT_MAX_AGE_SCHEMA,
+        },
     }
 )]
 #[derive(Serialize, Deserialize, Updater, Clone, PartialEq)]
@@ -233,6 +272,18 @@ pub struct VerificationJobConfig {
     /// how deep the verify should go from the `ns` level downwards. Passing 0 verifies only the
     /// snapshots on the same level as the passed `ns`, or the datastore root if none.
     pub max_depth: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// number of threads used for decoding and verifying chunks
+    pub worker_threads: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// maximum random delay added to the scheduled start time
+    pub randomized_delay: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// limit for reading chunks from the datastore (bytes per second)
+    pub read_limit: Option<HumanByte>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// resume from a checkpoint of verified chunks, if it is not older than this many hours
+    pub checkpoint_max_age: Option<i64>,
 }
 
 impl VerificationJobConfig {
@@ -644,6 +695,10 @@ pub struct SyncJobStatus {
             schema: crate::PRUNE_SCHEMA_KEEP_YEARLY,
             optional: true,
         },
+        "keep-last-verified": {
+            schema: crate::PRUNE_SCHEMA_KEEP_LAST_VERIFIED,
+            optional: true,
+        },
     }
 )]
 #[derive(Serialize, Deserialize, Default, Updater, Clone, PartialEq)]
@@ -662,6 +717,8 @@ pub struct KeepOptions {
     pub keep_monthly: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub keep_yearly: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_last_verified: Option<u64>,
 }
 
 impl KeepOptions {
@@ -672,6 +729,7 @@ impl KeepOptions {
             + self.keep_weekly.unwrap_or(0)
             + self.keep_monthly.unwrap_or(0)
             + self.keep_yearly.unwrap_or(0)
+            + self.keep_last_verified.unwrap_or(0)
             > 0
     }
 }
@@ -117,6 +117,9 @@ pub use proxmox_auth_api::types::{
 mod user;
 pub use user::*;
 
+mod worker_types;
+pub use worker_types::*;
+
 pub use proxmox_schema::upid::*;
 
 mod crypto;
@@ -302,6 +305,18 @@ pub enum TaskStateType {
     Unknown,
 }
 
+#[api()]
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Sort order of task lists.
+pub enum TaskListSortOrder {
+    /// Most recently started tasks first
+    #[default]
+    Starttime,
+    /// Longest running tasks first
+    Runtime,
+}
+
 #[api(
     properties: {
         upid: { schema: UPID::API_SCHEMA },
@@ -331,6 +346,12 @@ pub struct TaskListItem {
     /// Task end status
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<String>,
+    /// Number of warnings of a finished task, 0 unless it ended with warnings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<u64>,
+    /// The task run time in seconds (up to now for running tasks)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<i64>,
 }
 
 pub const NODE_TASKS_LIST_TASKS_RETURN_TYPE: ReturnType = ReturnType {
@@ -6,7 +6,7 @@
 
 use std::str::FromStr;
 
-use anyhow::Error;
+use anyhow::{bail, Error};
 use serde::{Deserialize, Serialize};
 
 use proxmox_schema::{api, ApiStringFormat, Schema, StringSchema, Updater};
@@ -14,7 +14,7 @@ use proxmox_schema::{api, ApiStringFormat, Schema, StringSchema, Updater};
 use proxmox_time::{CalendarEvent, TimeSpan};
 
 use crate::{
-    PROXMOX_SAFE_ID_FORMAT, SINGLE_LINE_COMMENT_FORMAT, SINGLE_LINE_COMMENT_SCHEMA,
+    PROXMOX_SAFE_ID_FORMAT, SINGLE_LINE_COMMENT_REGEX, SINGLE_LINE_COMMENT_SCHEMA,
     TAPE_ENCRYPTION_KEY_FINGERPRINT_SCHEMA,
 };
 
@@ -24,10 +24,59 @@ pub const MEDIA_POOL_NAME_SCHEMA: Schema = StringSchema::new("Media pool name.")
     .max_length(32)
     .schema();
 
+/// Check a media set naming template for invalid strftime() time format specifications.
+///
+/// Besides the strftime() conversions (including the glibc flags, field width and 'E'/'O'
+/// modifiers), the template may contain '%id%', which gets replaced by the media set UUID.
+pub fn verify_media_set_naming_template(template: &str) -> Result<(), Error> {
+    const CONVERSIONS: &str = "aAbBcCdDeFgGhHIjklmMnpPrRsStTuUVwWxXyYzZ+%";
+
+    if !SINGLE_LINE_COMMENT_REGEX.is_match(template) {
+        bail!("template contains control characters");
+    }
+
+    let template = template.replace("%id%", "");
+
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        while chars.next_if(|c| "_-0^#".contains(*c)).is_some() {}
+        while chars.next_if(char::is_ascii_digit).is_some() {}
+        chars.next_if(|c| *c == 'E' || *c == 'O');
+        match chars.next() {
+            Some(c) if CONVERSIONS.contains(c) => (),
+            Some(c) => bail!("unknown time format specification '%{c}'"),
+            None => bail!("incomplete time format specification at end of template"),
+        }
+    }
+
+    proxmox_time::strftime_utc(&template, 0)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_media_set_naming_template() -> Result<(), Error> {
+    for template in ["%c", "%id%", "set-%Y-%m-%d_%H:%M", "%-d.%_m.%EY %Oe %%"] {
+        verify_media_set_naming_template(template)?;
+    }
+
+    for template in ["%Q", "set-%", "%Y-%m-%i", "%-5K"] {
+        assert!(verify_media_set_naming_template(template).is_err());
+    }
+
+    Ok(())
+}
+
+pub const MEDIA_SET_NAMING_TEMPLATE_FORMAT: ApiStringFormat =
+    ApiStringFormat::VerifyFn(verify_media_set_naming_template);
+
 pub const MEDIA_SET_NAMING_TEMPLATE_SCHEMA: Schema = StringSchema::new(
     "Media set naming template (may contain strftime() time format specifications).",
 )
-.format(&SINGLE_LINE_COMMENT_FORMAT)
+.format(&MEDIA_SET_NAMING_TEMPLATE_FORMAT)
 .min_length(2)
 .max_length(64)
 .schema();
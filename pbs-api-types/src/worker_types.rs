@@ -0,0 +1,258 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{format_err, Error};
+use proxmox_schema::api;
+use serde::{Deserialize, Serialize};
+
+/// Worker task types produced by the server.
+///
+/// Use [`WorkerType::as_str`] when spawning a worker, so that every task type the server creates
+/// is listed here. The labels correspond to the task descriptions used by the web interface
+/// (`www/Utils.js`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WorkerType {
+    AcmeDeactivate,
+    AcmeNewCert,
+    AcmeRegister,
+    AcmeRenewCert,
+    AcmeRevokeCert,
+    AcmeUpdate,
+    AptUpdate,
+    Backup,
+    BarcodeLabelMedia,
+    Benchmark,
+    CatalogMedia,
+    CleanDrive,
+    CreateDatastore,
+    DeleteDatastore,
+    DirCreate,
+    DiskInit,
+    EjectMedia,
+    FormatMedia,
+    GarbageCollection,
+    InventoryUpdate,
+    LabelMedia,
+    LoadMedia,
+    LogRotate,
+    Prune,
+    PruneJob,
+    Reader,
+    RealmSync,
+    RewindMedia,
+    SrvReload,
+    SrvRestart,
+    SrvStart,
+    SrvStop,
+    Sync,
+    SyncJob,
+    TapeBackup,
+    TapeBackupJob,
+    TapeRestore,
+    TermProxy,
+    UnloadMedia,
+    VerificationJob,
+    Verify,
+    VerifyGroup,
+    VerifySnapshot,
+    WipeDisk,
+    ZfsCreate,
+}
+
+impl WorkerType {
+    /// All known worker types, sorted by their name.
+    pub const ALL: &'static [WorkerType] = &[
+        WorkerType::AcmeDeactivate,
+        WorkerType::AcmeNewCert,
+        WorkerType::AcmeRegister,
+        WorkerType::AcmeRenewCert,
+        WorkerType::AcmeRevokeCert,
+        WorkerType::AcmeUpdate,
+        WorkerType::AptUpdate,
+        WorkerType::Backup,
+        WorkerType::BarcodeLabelMedia,
+        WorkerType::Benchmark,
+        WorkerType::CatalogMedia,
+        WorkerType::CleanDrive,
+        WorkerType::CreateDatastore,
+        WorkerType::DeleteDatastore,
+        WorkerType::DirCreate,
+        WorkerType::DiskInit,
+        WorkerType::EjectMedia,
+        WorkerType::FormatMedia,
+        WorkerType::GarbageCollection,
+        WorkerType::InventoryUpdate,
+        WorkerType::LabelMedia,
+        WorkerType::LoadMedia,
+        WorkerType::LogRotate,
+        WorkerType::Prune,
+        WorkerType::PruneJob,
+        WorkerType::Reader,
+        WorkerType::RealmSync,
+        WorkerType::RewindMedia,
+        WorkerType::SrvReload,
+        WorkerType::SrvRestart,
+        WorkerType::SrvStart,
+        WorkerType::SrvStop,
+        WorkerType::Sync,
+        WorkerType::SyncJob,
+        WorkerType::TapeBackup,
+        WorkerType::TapeBackupJob,
+        WorkerType::TapeRestore,
+        WorkerType::TermProxy,
+        WorkerType::UnloadMedia,
+        WorkerType::VerificationJob,
+        WorkerType::Verify,
+        WorkerType::VerifyGroup,
+        WorkerType::VerifySnapshot,
+        WorkerType::WipeDisk,
+        WorkerType::ZfsCreate,
+    ];
+
+    /// The worker type as used in the UPID.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            WorkerType::AcmeDeactivate => "acme-deactivate",
+            WorkerType::AcmeNewCert => "acme-new-cert",
+            WorkerType::AcmeRegister => "acme-register",
+            WorkerType::AcmeRenewCert => "acme-renew-cert",
+            WorkerType::AcmeRevokeCert => "acme-revoke-cert",
+            WorkerType::AcmeUpdate => "acme-update",
+            WorkerType::AptUpdate => "aptupdate",
+            WorkerType::Backup => "backup",
+            WorkerType::BarcodeLabelMedia => "barcode-label-media",
+            WorkerType::Benchmark => "benchmark",
+            WorkerType::CatalogMedia => "catalog-media",
+            WorkerType::CleanDrive => "clean-drive",
+            WorkerType::CreateDatastore => "create-datastore",
+            WorkerType::DeleteDatastore => "delete-datastore",
+            WorkerType::DirCreate => "dircreate",
+            WorkerType::DiskInit => "diskinit",
+            WorkerType::EjectMedia => "eject-media",
+            WorkerType::FormatMedia => "format-media",
+            WorkerType::GarbageCollection => "garbage_collection",
+            WorkerType::InventoryUpdate => "inventory-update",
+            WorkerType::LabelMedia => "label-media",
+            WorkerType::LoadMedia => "load-media",
+            WorkerType::LogRotate => "logrotate",
+            WorkerType::Prune => "prune",
+            WorkerType::PruneJob => "prunejob",
+            WorkerType::Reader => "reader",
+            WorkerType::RealmSync => "realm-sync",
+            WorkerType::RewindMedia => "rewind-media",
+            WorkerType::SrvReload => "srvreload",
+            WorkerType::SrvRestart => "srvrestart",
+            WorkerType::SrvStart => "srvstart",
+            WorkerType::SrvStop => "srvstop",
+            WorkerType::Sync => "sync",
+            WorkerType::SyncJob => "syncjob",
+            WorkerType::TapeBackup => "tape-backup",
+            WorkerType::TapeBackupJob => "tape-backup-job",
+            WorkerType::TapeRestore => "tape-restore",
+            WorkerType::TermProxy => "termproxy",
+            WorkerType::UnloadMedia => "unload-media",
+            WorkerType::VerificationJob => "verificationjob",
+            WorkerType::Verify => "verify",
+            WorkerType::VerifyGroup => "verify_group",
+            WorkerType::VerifySnapshot => "verify_snapshot",
+            WorkerType::WipeDisk => "wipedisk",
+            WorkerType::ZfsCreate => "zfscreate",
+        }
+    }
+
+    /// Human readable label.
+    pub const fn label(self) -> &'static str {
+        match self {
+            WorkerType::AcmeDeactivate => "Deactivate ACME Account",
+            WorkerType::AcmeNewCert => "Order Certificate",
+            WorkerType::AcmeRegister => "Register ACME Account",
+            WorkerType::AcmeRenewCert => "Renew Certificate",
+            WorkerType::AcmeRevokeCert => "Revoke Certificate",
+            WorkerType::AcmeUpdate => "Update ACME Account",
+            WorkerType::AptUpdate => "Update Package Database",
+            WorkerType::Backup => "Backup",
+            WorkerType::BarcodeLabelMedia => "Barcode-Label Media",
+            WorkerType::Benchmark => "Benchmark",
+            WorkerType::CatalogMedia => "Catalog Media",
+            WorkerType::CleanDrive => "Clean Drive",
+            WorkerType::CreateDatastore => "Create Datastore",
+            WorkerType::DeleteDatastore => "Remove Datastore",
+            WorkerType::DirCreate => "Create Directory Storage",
+            WorkerType::DiskInit => "Initialize Disk",
+            WorkerType::EjectMedia => "Eject Media",
+            WorkerType::FormatMedia => "Format Media",
+            WorkerType::GarbageCollection => "Garbage Collect",
+            WorkerType::InventoryUpdate => "Inventory Update",
+            WorkerType::LabelMedia => "Label Media",
+            WorkerType::LoadMedia => "Load Media",
+            WorkerType::LogRotate => "Log Rotation",
+            WorkerType::Prune => "Prune",
+            WorkerType::PruneJob => "Prune Job",
+            WorkerType::Reader => "Read Objects",
+            WorkerType::RealmSync => "User Sync",
+            WorkerType::RewindMedia => "Rewind Media",
+            WorkerType::SrvReload => "Reload Service",
+            WorkerType::SrvRestart => "Restart Service",
+            WorkerType::SrvStart => "Start Service",
+            WorkerType::SrvStop => "Stop Service",
+            WorkerType::Sync => "Remote Sync",
+            WorkerType::SyncJob => "Sync Job",
+            WorkerType::TapeBackup => "Tape Backup",
+            WorkerType::TapeBackupJob => "Tape Backup Job",
+            WorkerType::TapeRestore => "Tape Restore",
+            WorkerType::TermProxy => "Console",
+            WorkerType::UnloadMedia => "Unload Media",
+            WorkerType::VerificationJob => "Verify Job",
+            WorkerType::Verify => "Verification",
+            WorkerType::VerifyGroup => "Group Verification",
+            WorkerType::VerifySnapshot => "Snapshot Verification",
+            WorkerType::WipeDisk => "Wipe Disk",
+            WorkerType::ZfsCreate => "Create ZFS Storage",
+        }
+    }
+}
+
+impl fmt::Display for WorkerType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WorkerType {
+    type Err = Error;
+
+    fn from_str(worker_type: &str) -> Result<Self, Error> {
+        Self::ALL
+            .iter()
+            .find(|ty| ty.as_str() == worker_type)
+            .copied()
+            .ok_or_else(|| format_err!("unknown worker type '{worker_type}'"))
+    }
+}
+
+/// Returns the label of a known worker type.
+pub fn worker_type_label(worker_type: &str) -> Option<&'static str> {
+    worker_type
+        .parse::<WorkerType>()
+        .ok()
+        .map(WorkerType::label)
+}
+
+/// Returns true if `worker_type` is a known [`WorkerType`].
+///
+/// Task logs can contain types of older or newer versions, so unknown types should usually be
+/// tolerated instead of treated as an error.
+pub fn is_known_worker_type(worker_type: &str) -> bool {
+    worker_type.parse::<WorkerType>().is_ok()
+}
+
+#[api]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+/// A worker task type and its label
+pub struct WorkerTypeInfo {
+    /// The worker type as used in the UPID
+    pub worker_type: String,
+    /// Human readable label
+    pub label: String,
+}
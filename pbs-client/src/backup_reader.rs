@@ -119,6 +119,12 @@ impl BackupReader {
         self.h2.download(path, Some(param), output).await
     }
 
+    /// List the chunk digests and sizes of an index file, without downloading any chunk data
+    pub async fn index_digests(&self, file_name: &str) -> Result<Value, Error> {
+        let param = json!({ "file-name": file_name });
+        self.h2.get("index_digests", Some(param)).await
+    }
+
     pub fn force_close(self) {
         self.abort.abort();
     }
@@ -162,7 +168,7 @@ impl BackupReader {
 
         tmpfile.seek(SeekFrom::Start(0))?;
 
-        DataBlobReader::new(tmpfile, self.crypt_config.clone())
+        DataBlobReader::new(tmpfile, self.crypt_config.clone(), None)
     }
 
     /// Download dynamic index file
@@ -51,6 +51,7 @@ pub struct UploadOptions {
     pub compress: bool,
     pub encrypt: bool,
     pub fixed_size: Option<u64>,
+    pub fixed_chunk_size: Option<u64>,
 }
 
 struct UploadStats {
@@ -271,6 +272,9 @@ impl BackupWriter {
         let mut param = json!({ "archive-name": archive_name });
         let prefix = if let Some(size) = options.fixed_size {
             param["size"] = size.into();
+            if let Some(chunk_size) = options.fixed_chunk_size {
+                param["chunk-size"] = chunk_size.into();
+            }
             "fixed"
         } else {
             "dynamic"
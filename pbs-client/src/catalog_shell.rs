@@ -1091,7 +1091,7 @@ impl<'a> ExtractorState<'a> {
                 self.extract_file(file).await?;
                 self.dir_stack.pop();
             }
-            (true, DirEntryAttribute::Symlink)
+            (true, DirEntryAttribute::Symlink { .. })
             | (true, DirEntryAttribute::BlockDevice)
             | (true, DirEntryAttribute::CharDevice)
             | (true, DirEntryAttribute::Fifo)
@@ -1138,7 +1138,7 @@ impl<'a> ExtractorState<'a> {
     ) -> Result<(), Error> {
         let file_name = CString::new(entry.file_name().as_bytes())?;
         match (catalog_attr, entry.kind()) {
-            (DirEntryAttribute::Symlink, pxar::EntryKind::Symlink(symlink)) => {
+            (DirEntryAttribute::Symlink { .. }, pxar::EntryKind::Symlink(symlink)) => {
                 block_in_place(|| {
                     self.extractor.extract_symlink(
                         &file_name,
@@ -1147,7 +1147,7 @@ impl<'a> ExtractorState<'a> {
                     )
                 })
             }
-            (DirEntryAttribute::Symlink, _) => {
+            (DirEntryAttribute::Symlink { .. }, _) => {
                 bail!(
                     "catalog symlink {:?} not a symlink in the archive",
                     self.path()
@@ -1,5 +1,5 @@
 use std::collections::{HashMap, HashSet};
-use std::ffi::{CStr, CString, OsStr};
+use std::ffi::{CStr, CString, OsStr, OsString};
 use std::fmt;
 use std::io::{self, Read};
 use std::os::unix::ffi::OsStrExt;
@@ -395,12 +395,6 @@ impl Archiver {
         patterns_count: usize,
     ) -> Result<(), Error> {
         let content = generate_pxar_excludes_cli(&self.patterns[..patterns_count]);
-        if let Some(ref catalog) = self.catalog {
-            catalog
-                .lock()
-                .unwrap()
-                .add_file(file_name, content.len() as u64, 0)?;
-        }
 
         let mut metadata = Metadata::default();
         metadata.stat.mode = pxar::format::mode::IFREG | 0o600;
@@ -408,8 +402,21 @@ impl Archiver {
         let mut file = encoder
             .create_file(&metadata, ".pxarexclude-cli", content.len() as u64)
             .await?;
+        let offset = file.file_offset();
         file.write_all(&content).await?;
 
+        if let Some(ref catalog) = self.catalog {
+            catalog.lock().unwrap().add_file_with_offset(
+                file_name,
+                content.len() as u64,
+                0,
+                pxar::format::mode::IFREG as u32 | 0o600,
+                0,
+                0,
+                offset.raw(),
+            )?;
+        }
+
         Ok(())
     }
 
@@ -578,17 +585,22 @@ impl Archiver {
                 }
 
                 let file_size = stat.st_size as u64;
-                if let Some(ref catalog) = self.catalog {
-                    catalog
-                        .lock()
-                        .unwrap()
-                        .add_file(c_file_name, file_size, stat.st_mtime)?;
-                }
-
                 let offset: LinkOffset = self
                     .add_regular_file(encoder, fd, file_name, &metadata, file_size)
                     .await?;
 
+                if let Some(ref catalog) = self.catalog {
+                    catalog.lock().unwrap().add_file_with_offset(
+                        c_file_name,
+                        file_size,
+                        stat.st_mtime,
+                        stat.st_mode,
+                        stat.st_uid,
+                        stat.st_gid,
+                        offset.raw(),
+                    )?;
+                }
+
                 if stat.st_nlink > 1 {
                     self.hardlinks
                         .insert(link_info, (self.path.clone(), offset));
@@ -625,11 +637,16 @@ impl Archiver {
                 Ok(encoder.add_fifo(&metadata, file_name).await?)
             }
             mode::IFLNK => {
+                let dest = nix::fcntl::readlinkat(fd.as_raw_fd(), &b""[..])?;
                 if let Some(ref catalog) = self.catalog {
-                    catalog.lock().unwrap().add_symlink(c_file_name)?;
+                    let target = CString::new(dest.as_bytes())?;
+                    catalog
+                        .lock()
+                        .unwrap()
+                        .add_symlink_with_target(c_file_name, &target)?;
                 }
 
-                self.add_symlink(encoder, fd, file_name, &metadata).await
+                self.add_symlink(encoder, file_name, &metadata, dest).await
             }
             mode::IFBLK => {
                 if let Some(ref catalog) = self.catalog {
@@ -739,11 +756,10 @@ impl Archiver {
     async fn add_symlink<T: SeqWrite + Send>(
         &mut self,
         encoder: &mut Encoder<'_, T>,
-        fd: OwnedFd,
         file_name: &Path,
         metadata: &Metadata,
+        dest: OsString,
     ) -> Result<(), Error> {
-        let dest = nix::fcntl::readlinkat(fd.as_raw_fd(), &b""[..])?;
         encoder.add_symlink(metadata, file_name, dest).await?;
         Ok(())
     }
@@ -26,6 +26,8 @@ use proxmox_sys::fs::{create_path, CreateOptions};
 
 use proxmox_compression::zip::{ZipEncoder, ZipEntry};
 
+use pbs_datastore::catalog::{DirEntry, DirEntryAttribute};
+
 use crate::pxar::dir_stack::PxarDirStack;
 use crate::pxar::metadata;
 use crate::pxar::Flags;
@@ -1300,3 +1302,140 @@ where
         }
     }
 }
+
+/// Returns a reader over exactly the contents of the regular file described by a catalog entry.
+///
+/// `reader` has to provide the decoded pxar archive the catalog was created for (for example a
+/// `BufferedDynamicReader`). This allows restoring a single file without decoding the whole
+/// archive, but requires a catalog which recorded the file offsets (format version 1.3 or newer).
+pub fn open_catalog_file_contents<R>(entry: &DirEntry, mut reader: R) -> Result<io::Take<R>, Error>
+where
+    R: io::Read + io::Seek,
+{
+    let (size, offset) = match entry.attr {
+        DirEntryAttribute::File {
+            size,
+            offset: Some(offset),
+            ..
+        } => (size, offset),
+        DirEntryAttribute::File { offset: None, .. } => {
+            bail!(
+                "catalog entry {:?} has no archive offset",
+                OsStr::from_bytes(&entry.name)
+            )
+        }
+        _ => bail!(
+            "catalog entry {:?} is not a regular file",
+            OsStr::from_bytes(&entry.name)
+        ),
+    };
+
+    reader.seek(io::SeekFrom::Start(offset))?;
+
+    // the file entry starts with its name, followed by the metadata and the payload
+    let mut expected_htype = Some(pxar::format::PXAR_FILENAME);
+    loop {
+        let mut header = [0u8; 16];
+        reader.read_exact(&mut header)?;
+        let htype = u64::from_le_bytes(header[0..8].try_into().unwrap());
+        let full_size = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        let content_size = full_size
+            .checked_sub(header.len() as u64)
+            .ok_or_else(|| format_err!("invalid pxar header size {full_size}"))?;
+
+        if let Some(expected) = expected_htype.take() {
+            if htype != expected {
+                bail!("no pxar file entry at offset {offset}");
+            }
+        } else {
+            match htype {
+                pxar::format::PXAR_PAYLOAD => {
+                    if content_size != size {
+                        bail!(
+                            "pxar payload size {content_size} does not match catalog size {size}"
+                        );
+                    }
+                    return Ok(reader.take(size));
+                }
+                pxar::format::PXAR_FILENAME
+                | pxar::format::PXAR_GOODBYE
+                | pxar::format::PXAR_HARDLINK => {
+                    bail!("pxar entry at offset {offset} has no payload");
+                }
+                _ => (),
+            }
+        }
+
+        reader.seek(io::SeekFrom::Current(i64::try_from(content_size)?))?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Read, Write};
+
+    use pbs_datastore::catalog::{DirEntry, DirEntryAttribute};
+
+    use super::open_catalog_file_contents;
+
+    fn file_entry(name: &str, size: u64, offset: Option<u64>) -> DirEntry {
+        DirEntry {
+            name: name.as_bytes().to_vec(),
+            attr: DirEntryAttribute::File {
+                size,
+                mtime: 0,
+                mode: None,
+                uid: None,
+                gid: None,
+                offset,
+            },
+        }
+    }
+
+    #[test]
+    fn test_open_catalog_file_contents() {
+        let files: [(&str, &[u8]); 3] = [
+            ("a", b"first file"),
+            ("b", b""),
+            ("c", b"some other content"),
+        ];
+
+        let mut archive = Vec::new();
+        let mut offsets = Vec::new();
+        {
+            let mut metadata = pxar::Metadata::default();
+            metadata.stat.mode = pxar::format::mode::IFDIR | 0o755;
+            let writer = pxar::encoder::sync::StandardWriter::new(&mut archive);
+            let mut encoder = pxar::encoder::sync::Encoder::new(writer, &metadata).unwrap();
+
+            metadata.stat.mode = pxar::format::mode::IFREG | 0o644;
+            for (name, content) in files {
+                let mut file = encoder
+                    .create_file(&metadata, name, content.len() as u64)
+                    .unwrap();
+                let offset = file.file_offset().raw();
+                file.write_all(content).unwrap();
+                offsets.push(offset);
+            }
+            encoder.finish().unwrap();
+        }
+
+        for (offset, (name, content)) in offsets.iter().zip(files) {
+            let entry = file_entry(name, content.len() as u64, Some(*offset));
+            let mut data = Vec::new();
+            open_catalog_file_contents(&entry, Cursor::new(&archive))
+                .unwrap()
+                .read_to_end(&mut data)
+                .unwrap();
+            assert_eq!(data, content);
+        }
+
+        // wrong size, no offset, and an offset not pointing to a file entry
+        let bad_size = file_entry("a", 3, Some(offsets[0]));
+        assert!(open_catalog_file_contents(&bad_size, Cursor::new(&archive)).is_err());
+        let no_offset = file_entry("a", 10, None);
+        assert!(open_catalog_file_contents(&no_offset, Cursor::new(&archive)).is_err());
+        let bad_offset = file_entry("a", 10, Some(0));
+        assert!(open_catalog_file_contents(&bad_offset, Cursor::new(&archive)).is_err());
+    }
+}
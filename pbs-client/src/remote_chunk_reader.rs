@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
@@ -23,6 +23,8 @@ pub struct RemoteChunkReader {
     crypt_mode: CryptMode,
     cache_hint: Arc<HashMap<[u8; 32], usize>>,
     cache: Arc<Mutex<HashMap<[u8; 32], Vec<u8>>>>,
+    lru_cache: Option<Arc<Mutex<ChunkLruCache>>>,
+    prefetching: Arc<Mutex<HashSet<[u8; 32]>>>,
 }
 
 impl RemoteChunkReader {
@@ -41,6 +43,78 @@ impl RemoteChunkReader {
             crypt_mode,
             cache_hint: Arc::new(cache_hint),
             cache: Arc::new(Mutex::new(HashMap::new())),
+            lru_cache: None,
+            prefetching: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Create a new instance with an additional, size bounded LRU cache.
+    ///
+    /// Chunks listed in ``cache_hint`` are cached and kept in RAM. All other chunks are kept in
+    /// a least recently used cache holding up to ``max_bytes`` of decoded chunk data.
+    pub fn new_with_lru(
+        client: Arc<BackupReader>,
+        crypt_config: Option<Arc<CryptConfig>>,
+        crypt_mode: CryptMode,
+        cache_hint: HashMap<[u8; 32], usize>,
+        max_bytes: usize,
+    ) -> Self {
+        let mut reader = Self::new(client, crypt_config, crypt_mode, cache_hint);
+        reader.lru_cache = Some(Arc::new(Mutex::new(ChunkLruCache::new(max_bytes))));
+        reader
+    }
+
+    /// Start downloading chunks in the background, before they get requested.
+    ///
+    /// ``digests`` should list the upcoming chunks in the order they will be read, of which the
+    /// first ``depth`` are downloaded concurrently and stored in the cache. Chunks which are
+    /// already cached or currently prefetched are skipped. Prefetched chunks are subject to the
+    /// same caching rules as regular reads, so this is only useful for hinted chunks or with
+    /// an LRU cache (see ``new_with_lru``). Download errors are ignored, the chunk is simply
+    /// fetched again when it is read.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn prefetch(&self, digests: &[[u8; 32]], depth: usize) {
+        for digest in digests.iter().take(depth) {
+            if self.is_cached(digest) || !self.prefetching.lock().unwrap().insert(*digest) {
+                continue;
+            }
+
+            let reader = self.clone();
+            let digest = *digest;
+            tokio::spawn(async move {
+                if let Err(err) = AsyncReadChunk::read_chunk(&reader, &digest).await {
+                    log::debug!("prefetching chunk {} failed - {err}", hex::encode(digest));
+                }
+                reader.prefetching.lock().unwrap().remove(&digest);
+            });
+        }
+    }
+
+    fn is_cached(&self, digest: &[u8; 32]) -> bool {
+        if (*self.cache.lock().unwrap()).contains_key(digest) {
+            return true;
+        }
+        match self.lru_cache {
+            Some(ref lru_cache) => lru_cache.lock().unwrap().contains(digest),
+            None => false,
+        }
+    }
+
+    fn lookup_cache(&self, digest: &[u8; 32]) -> Option<Vec<u8>> {
+        if let Some(raw_data) = (*self.cache.lock().unwrap()).get(digest) {
+            return Some(raw_data.to_vec());
+        }
+        let lru_cache = self.lru_cache.as_ref()?;
+        let mut lru_cache = lru_cache.lock().unwrap();
+        lru_cache.get(digest).map(|raw_data| raw_data.to_vec())
+    }
+
+    fn insert_cache(&self, digest: &[u8; 32], raw_data: &[u8]) {
+        if self.cache_hint.contains_key(digest) {
+            (*self.cache.lock().unwrap()).insert(*digest, raw_data.to_vec());
+        } else if let Some(ref lru_cache) = self.lru_cache {
+            lru_cache.lock().unwrap().insert(*digest, raw_data.to_vec());
         }
     }
 
@@ -75,18 +149,15 @@ impl ReadChunk for RemoteChunkReader {
     }
 
     fn read_chunk(&self, digest: &[u8; 32]) -> Result<Vec<u8>, Error> {
-        if let Some(raw_data) = (*self.cache.lock().unwrap()).get(digest) {
-            return Ok(raw_data.to_vec());
+        if let Some(raw_data) = self.lookup_cache(digest) {
+            return Ok(raw_data);
         }
 
         let chunk = ReadChunk::read_raw_chunk(self, digest)?;
 
         let raw_data = chunk.decode(self.crypt_config.as_ref().map(Arc::as_ref), Some(digest))?;
 
-        let use_cache = self.cache_hint.contains_key(digest);
-        if use_cache {
-            (*self.cache.lock().unwrap()).insert(*digest, raw_data.to_vec());
-        }
+        self.insert_cache(digest, &raw_data);
 
         Ok(raw_data)
     }
@@ -105,8 +176,8 @@ impl AsyncReadChunk for RemoteChunkReader {
         digest: &'a [u8; 32],
     ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, Error>> + Send + 'a>> {
         Box::pin(async move {
-            if let Some(raw_data) = (*self.cache.lock().unwrap()).get(digest) {
-                return Ok(raw_data.to_vec());
+            if let Some(raw_data) = self.lookup_cache(digest) {
+                return Ok(raw_data);
             }
 
             let chunk = Self::read_raw_chunk(self, digest).await?;
@@ -114,12 +185,107 @@ impl AsyncReadChunk for RemoteChunkReader {
             let raw_data =
                 chunk.decode(self.crypt_config.as_ref().map(Arc::as_ref), Some(digest))?;
 
-            let use_cache = self.cache_hint.contains_key(digest);
-            if use_cache {
-                (*self.cache.lock().unwrap()).insert(*digest, raw_data.to_vec());
-            }
+            self.insert_cache(digest, &raw_data);
 
             Ok(raw_data)
         })
     }
 }
+
+/// Least recently used cache for decoded chunks, bounded by the total chunk size.
+struct ChunkLruCache {
+    max_bytes: usize,
+    cached_bytes: usize,
+    // monotonic access counter, used as key for the access order
+    counter: u64,
+    entries: HashMap<[u8; 32], (u64, Vec<u8>)>,
+    access_order: BTreeMap<u64, [u8; 32]>,
+}
+
+impl ChunkLruCache {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            cached_bytes: 0,
+            counter: 0,
+            entries: HashMap::new(),
+            access_order: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, digest: &[u8; 32]) -> Option<&Vec<u8>> {
+        let (last_access, data) = self.entries.get_mut(digest)?;
+        self.access_order.remove(last_access);
+        self.counter += 1;
+        *last_access = self.counter;
+        self.access_order.insert(self.counter, *digest);
+        Some(data)
+    }
+
+    fn insert(&mut self, digest: [u8; 32], data: Vec<u8>) {
+        if data.len() > self.max_bytes {
+            return;
+        }
+        self.remove(&digest);
+
+        while self.cached_bytes + data.len() > self.max_bytes {
+            match self.access_order.first_key_value() {
+                Some((_, oldest)) => {
+                    let oldest = *oldest;
+                    self.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+
+        self.counter += 1;
+        self.cached_bytes += data.len();
+        self.access_order.insert(self.counter, digest);
+        self.entries.insert(digest, (self.counter, data));
+    }
+
+    fn contains(&self, digest: &[u8; 32]) -> bool {
+        self.entries.contains_key(digest)
+    }
+
+    fn remove(&mut self, digest: &[u8; 32]) {
+        if let Some((last_access, data)) = self.entries.remove(digest) {
+            self.access_order.remove(&last_access);
+            self.cached_bytes -= data.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChunkLruCache;
+
+    #[test]
+    fn test_chunk_lru_cache_eviction() {
+        let mut cache = ChunkLruCache::new(25);
+
+        cache.insert([1u8; 32], vec![1u8; 10]);
+        cache.insert([2u8; 32], vec![2u8; 10]);
+        assert_eq!(cache.cached_bytes, 20);
+
+        // touch the first chunk, so the second one is the least recently used
+        assert!(cache.get(&[1u8; 32]).is_some());
+
+        cache.insert([3u8; 32], vec![3u8; 10]);
+        assert_eq!(cache.cached_bytes, 20);
+        assert!(cache.get(&[2u8; 32]).is_none());
+        assert_eq!(cache.get(&[1u8; 32]), Some(&vec![1u8; 10]));
+        assert_eq!(cache.get(&[3u8; 32]), Some(&vec![3u8; 10]));
+
+        // chunks larger than the budget are never cached
+        cache.insert([4u8; 32], vec![4u8; 30]);
+        assert!(cache.get(&[4u8; 32]).is_none());
+        assert_eq!(cache.cached_bytes, 20);
+
+        // a large chunk evicts as many entries as needed
+        cache.insert([5u8; 32], vec![5u8; 25]);
+        assert_eq!(cache.cached_bytes, 25);
+        assert!(cache.get(&[1u8; 32]).is_none());
+        assert!(cache.get(&[3u8; 32]).is_none());
+    }
+}
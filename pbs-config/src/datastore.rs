@@ -100,3 +100,33 @@ pub fn complete_calendar_event(_arg: &str, _param: &HashMap<String, String>) ->
         .map(|s| String::from(*s))
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chunk_path_round_trip() -> Result<(), Error> {
+        let raw = "datastore: store1\n\tchunk-path /mnt/hdd/store1\n\tpath /mnt/ssd/store1\n\n\
+                   datastore: store2\n\tpath /backup/store2\n";
+
+        let data = CONFIG.parse("datastore.cfg", raw)?;
+
+        let store1: DataStoreConfig = data.lookup("datastore", "store1")?;
+        assert_eq!(store1.chunk_path.as_deref(), Some("/mnt/hdd/store1"));
+        assert_eq!(store1.chunk_base_path(), "/mnt/hdd/store1");
+
+        // old configs without the option use the datastore path
+        let store2: DataStoreConfig = data.lookup("datastore", "store2")?;
+        assert_eq!(store2.chunk_path, None);
+        assert_eq!(store2.chunk_base_path(), "/backup/store2");
+
+        let written = CONFIG.write("datastore.cfg", &data)?;
+        let reparsed = CONFIG.parse("datastore.cfg", &written)?;
+        let store1_reparsed: DataStoreConfig = reparsed.lookup("datastore", "store1")?;
+        assert!(store1 == store1_reparsed);
+        assert!(!written.contains("store2\n\tchunk-path"));
+
+        Ok(())
+    }
+}
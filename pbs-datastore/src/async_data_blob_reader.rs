@@ -0,0 +1,107 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::Error;
+use futures::ready;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use pbs_tools::crypt_config::CryptConfig;
+
+use crate::data_blob::{DataBlob, MAX_BLOB_SIZE};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+enum AsyncBlobReaderState {
+    Loading {
+        raw_data: Vec<u8>,
+        buffer: Box<[u8]>,
+    },
+    Decoded {
+        data: Vec<u8>,
+        pos: usize,
+    },
+}
+
+/// Async variant of [`DataBlobReader`](crate::DataBlobReader)
+///
+/// Handles the same blob formats, but since blobs are size limited, the encoded blob is read
+/// completely from the source first. It then gets verified and decoded in one go inside
+/// `block_in_place`, so that decompression and decryption do not stall the reactor. Reads are
+/// served from the decoded data.
+pub struct AsyncDataBlobReader<R> {
+    reader: R,
+    config: Option<Arc<CryptConfig>>,
+    state: AsyncBlobReaderState,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDataBlobReader<R> {
+    pub fn new(reader: R, config: Option<Arc<CryptConfig>>) -> Self {
+        Self {
+            reader,
+            config,
+            state: AsyncBlobReaderState::Loading {
+                raw_data: Vec::new(),
+                buffer: vec![0u8; READ_BUFFER_SIZE].into_boxed_slice(),
+            },
+        }
+    }
+
+    /// Returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn decode(raw_data: Vec<u8>, config: Option<&CryptConfig>) -> Result<Vec<u8>, Error> {
+        proxmox_async::runtime::block_in_place(|| {
+            let blob = DataBlob::from_raw(raw_data)?;
+            blob.verify_crc()?;
+            blob.decode(config, None)
+        })
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncDataBlobReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.state {
+                AsyncBlobReaderState::Loading { raw_data, buffer } => {
+                    let mut read_buf = ReadBuf::new(&mut buffer[..]);
+                    ready!(Pin::new(&mut this.reader).poll_read(cx, &mut read_buf))?;
+                    let data = read_buf.filled();
+
+                    if !data.is_empty() {
+                        if raw_data.len() + data.len() > MAX_BLOB_SIZE {
+                            return Poll::Ready(Err(std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                "data blob too large",
+                            )));
+                        }
+                        raw_data.extend_from_slice(data);
+                        continue;
+                    }
+
+                    let raw_data = std::mem::take(raw_data);
+                    let data = Self::decode(raw_data, this.config.as_deref()).map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("unable to decode blob - {err}"),
+                        )
+                    })?;
+                    this.state = AsyncBlobReaderState::Decoded { data, pos: 0 };
+                }
+                AsyncBlobReaderState::Decoded { data, pos } => {
+                    let count = buf.remaining().min(data.len() - *pos);
+                    buf.put_slice(&data[*pos..*pos + count]);
+                    *pos += count;
+                    return Poll::Ready(Ok(()));
+                }
+            }
+        }
+    }
+}
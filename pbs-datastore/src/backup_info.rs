@@ -267,6 +267,18 @@ impl BackupGroup {
         self.store
             .set_owner(&self.ns, self.as_ref(), auth_id, force)
     }
+
+    /// Change the owner of an existing backup group.
+    ///
+    /// This takes the group lock, so it fails while a backup into the group is running.
+    pub fn change_owner(&self, auth_id: &Authid) -> Result<(), Error> {
+        let _guard = lock_dir_noblock(
+            &self.full_group_path(),
+            "backup group",
+            "possible running backup",
+        )?;
+        self.set_owner(auth_id, true)
+    }
 }
 
 impl AsRef<pbs_api_types::BackupNamespace> for BackupGroup {
@@ -682,3 +694,42 @@ fn list_backup_files<P: ?Sized + nix::NixPath>(
 
     Ok(files)
 }
+
+#[test]
+fn test_change_owner() -> Result<(), Error> {
+    let mut path = std::fs::canonicalize(".")?; // we need absolute path
+    path.push(".testdir-change-owner");
+
+    if let Err(_e) = std::fs::remove_dir_all(&path) { /* ignore */ }
+
+    let user = nix::unistd::User::from_uid(nix::unistd::Uid::current())?.unwrap();
+    crate::ChunkStore::create(
+        "test",
+        &path,
+        user.uid,
+        user.gid,
+        None,
+        pbs_api_types::DatastoreFSyncLevel::None,
+    )?;
+    let datastore = unsafe { DataStore::open_path("test", &path, None)? };
+
+    let ns = BackupNamespace::root();
+    let group: pbs_api_types::BackupGroup = "host/test".parse()?;
+    let old_owner: Authid = "test@pbs".parse()?;
+    let new_owner: Authid = "other@pbs".parse()?;
+
+    let (_, guard) = datastore.create_locked_backup_group(&ns, &group, &old_owner)?;
+    let backup_group = datastore.backup_group(ns, group);
+
+    // a running backup holds the group lock
+    assert!(backup_group.change_owner(&new_owner).is_err());
+    assert_eq!(backup_group.get_owner()?, old_owner);
+
+    drop(guard);
+    backup_group.change_owner(&new_owner)?;
+    assert_eq!(backup_group.get_owner()?, new_owner);
+
+    if let Err(_e) = std::fs::remove_dir_all(&path) { /* ignore */ }
+
+    Ok(())
+}
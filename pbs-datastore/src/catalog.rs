@@ -2,16 +2,17 @@ use std::ffi::{CStr, CString, OsStr};
 use std::fmt;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
 
 use anyhow::{bail, format_err, Error};
 use serde::{Deserialize, Serialize};
 
-use pathpatterns::{MatchList, MatchType};
+use pathpatterns::{MatchEntry, MatchList, MatchType, PatternFlag};
 
 use proxmox_io::ReadExt;
 use proxmox_schema::api;
 
-use crate::file_formats::PROXMOX_CATALOG_FILE_MAGIC_1_0;
+use crate::file_formats::{PROXMOX_CATALOG_FILE_MAGIC_1_0, PROXMOX_CATALOG_FILE_MAGIC_1_1};
 
 /// Trait for writing file list catalogs.
 ///
@@ -20,8 +21,29 @@ use crate::file_formats::PROXMOX_CATALOG_FILE_MAGIC_1_0;
 pub trait BackupCatalogWriter {
     fn start_directory(&mut self, name: &CStr) -> Result<(), Error>;
     fn end_directory(&mut self) -> Result<(), Error>;
-    fn add_file(&mut self, name: &CStr, size: u64, mtime: i64) -> Result<(), Error>;
+    fn add_file(
+        &mut self,
+        name: &CStr,
+        size: u64,
+        mtime: i64,
+        mode: u32,
+        uid: u32,
+        gid: u32,
+    ) -> Result<(), Error>;
+    /// Like `add_file`, but also records the offset of the file entry in the pxar archive.
+    #[allow(clippy::too_many_arguments)]
+    fn add_file_with_offset(
+        &mut self,
+        name: &CStr,
+        size: u64,
+        mtime: i64,
+        mode: u32,
+        uid: u32,
+        gid: u32,
+        offset: u64,
+    ) -> Result<(), Error>;
     fn add_symlink(&mut self, name: &CStr) -> Result<(), Error>;
+    fn add_symlink_with_target(&mut self, name: &CStr, target: &CStr) -> Result<(), Error>;
     fn add_hardlink(&mut self, name: &CStr) -> Result<(), Error>;
     fn add_block_device(&mut self, name: &CStr) -> Result<(), Error>;
     fn add_char_device(&mut self, name: &CStr) -> Result<(), Error>;
@@ -65,7 +87,7 @@ impl From<&DirEntryAttribute> for CatalogEntryType {
         match value {
             DirEntryAttribute::Directory { .. } => CatalogEntryType::Directory,
             DirEntryAttribute::File { .. } => CatalogEntryType::File,
-            DirEntryAttribute::Symlink => CatalogEntryType::Symlink,
+            DirEntryAttribute::Symlink { .. } => CatalogEntryType::Symlink,
             DirEntryAttribute::Hardlink => CatalogEntryType::Hardlink,
             DirEntryAttribute::BlockDevice => CatalogEntryType::BlockDevice,
             DirEntryAttribute::CharDevice => CatalogEntryType::CharDevice,
@@ -92,11 +114,26 @@ pub struct DirEntry {
 }
 
 /// Used to specific additional attributes inside DirEntry
+///
+/// The `mode`, `uid`, `gid` and `offset` of files and the target of symlinks
+/// are only available for catalogs written in format version 1.1 or newer.
+/// The `offset` of a file points to its entry in the pxar archive.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DirEntryAttribute {
-    Directory { start: u64 },
-    File { size: u64, mtime: i64 },
-    Symlink,
+    Directory {
+        start: u64,
+    },
+    File {
+        size: u64,
+        mtime: i64,
+        mode: Option<u32>,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        offset: Option<u64>,
+    },
+    Symlink {
+        target: Option<Vec<u8>>,
+    },
     Hardlink,
     BlockDevice,
     CharDevice,
@@ -104,8 +141,34 @@ pub enum DirEntryAttribute {
     Socket,
 }
 
+/// Catalog format version, determined by the file magic
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CatalogVersion {
+    V1_0,
+    /// Adds mode, uid, gid and the pxar archive offset to file entries, and the target to
+    /// symlink entries
+    V1_1,
+}
+
+/// Optional, format version dependent attributes of an entry
+#[derive(Default)]
+struct EntryAttributes {
+    mode: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    target: Option<Vec<u8>>,
+    offset: Option<u64>,
+}
+
 impl DirEntry {
-    fn new(etype: CatalogEntryType, name: Vec<u8>, start: u64, size: u64, mtime: i64) -> Self {
+    fn new(
+        etype: CatalogEntryType,
+        name: Vec<u8>,
+        start: u64,
+        size: u64,
+        mtime: i64,
+        attributes: EntryAttributes,
+    ) -> Self {
         match etype {
             CatalogEntryType::Directory => DirEntry {
                 name,
@@ -113,11 +176,20 @@ impl DirEntry {
             },
             CatalogEntryType::File => DirEntry {
                 name,
-                attr: DirEntryAttribute::File { size, mtime },
+                attr: DirEntryAttribute::File {
+                    size,
+                    mtime,
+                    mode: attributes.mode,
+                    uid: attributes.uid,
+                    gid: attributes.gid,
+                    offset: attributes.offset,
+                },
             },
             CatalogEntryType::Symlink => DirEntry {
                 name,
-                attr: DirEntryAttribute::Symlink,
+                attr: DirEntryAttribute::Symlink {
+                    target: attributes.target,
+                },
             },
             CatalogEntryType::Hardlink => DirEntry {
                 name,
@@ -147,7 +219,7 @@ impl DirEntry {
         Some(match self.attr {
             DirEntryAttribute::Directory { .. } => pxar::mode::IFDIR,
             DirEntryAttribute::File { .. } => pxar::mode::IFREG,
-            DirEntryAttribute::Symlink => pxar::mode::IFLNK,
+            DirEntryAttribute::Symlink { .. } => pxar::mode::IFLNK,
             DirEntryAttribute::Hardlink => return None,
             DirEntryAttribute::BlockDevice => pxar::mode::IFBLK,
             DirEntryAttribute::CharDevice => pxar::mode::IFCHR,
@@ -197,21 +269,42 @@ impl DirInfo {
             }
             DirEntry {
                 name,
-                attr: DirEntryAttribute::File { size, mtime },
+                attr:
+                    DirEntryAttribute::File {
+                        size,
+                        mtime,
+                        mode,
+                        uid,
+                        gid,
+                        offset,
+                    },
             } => {
                 writer.write_all(&[CatalogEntryType::File as u8])?;
                 catalog_encode_u64(writer, name.len() as u64)?;
                 writer.write_all(name)?;
                 catalog_encode_u64(writer, *size)?;
                 catalog_encode_i64(writer, *mtime)?;
+                catalog_encode_optional_u32(writer, *mode)?;
+                catalog_encode_optional_u32(writer, *uid)?;
+                catalog_encode_optional_u32(writer, *gid)?;
+                // zero means no offset, otherwise the offset plus one
+                catalog_encode_u64(writer, offset.map(|v| v + 1).unwrap_or(0))?;
             }
             DirEntry {
                 name,
-                attr: DirEntryAttribute::Symlink,
+                attr: DirEntryAttribute::Symlink { target },
             } => {
                 writer.write_all(&[CatalogEntryType::Symlink as u8])?;
                 catalog_encode_u64(writer, name.len() as u64)?;
                 writer.write_all(name)?;
+                // zero means no target, otherwise the target length plus one
+                match target {
+                    Some(target) => {
+                        catalog_encode_u64(writer, target.len() as u64 + 1)?;
+                        writer.write_all(target)?;
+                    }
+                    None => catalog_encode_u64(writer, 0)?,
+                }
             }
             DirEntry {
                 name,
@@ -271,8 +364,14 @@ impl DirInfo {
         Ok((self.name, data))
     }
 
-    fn parse<C: FnMut(CatalogEntryType, &[u8], u64, u64, i64) -> Result<bool, Error>>(
+    /// Parse an encoded directory info block
+    ///
+    /// The `version` determines which optional attributes file and symlink entries include.
+    fn parse<
+        C: FnMut(CatalogEntryType, &[u8], u64, u64, i64, EntryAttributes) -> Result<bool, Error>,
+    >(
         data: &[u8],
+        version: CatalogVersion,
         mut callback: C,
     ) -> Result<(), Error> {
         let mut cursor = data;
@@ -300,14 +399,47 @@ impl DirInfo {
             let cont = match etype {
                 CatalogEntryType::Directory => {
                     let offset = catalog_decode_u64(&mut cursor)?;
-                    callback(etype, name, offset, 0, 0)?
+                    callback(etype, name, offset, 0, 0, EntryAttributes::default())?
                 }
                 CatalogEntryType::File => {
                     let size = catalog_decode_u64(&mut cursor)?;
                     let mtime = catalog_decode_i64(&mut cursor)?;
-                    callback(etype, name, 0, size, mtime)?
+                    let attributes = if version >= CatalogVersion::V1_1 {
+                        EntryAttributes {
+                            mode: catalog_decode_optional_u32(&mut cursor)?,
+                            uid: catalog_decode_optional_u32(&mut cursor)?,
+                            gid: catalog_decode_optional_u32(&mut cursor)?,
+                            offset: match catalog_decode_u64(&mut cursor)? {
+                                0 => None,
+                                v => Some(v - 1),
+                            },
+                            ..Default::default()
+                        }
+                    } else {
+                        EntryAttributes::default()
+                    };
+                    callback(etype, name, 0, size, mtime, attributes)?
                 }
-                _ => callback(etype, name, 0, 0, 0)?,
+                CatalogEntryType::Symlink if version >= CatalogVersion::V1_1 => {
+                    let target = match catalog_decode_u64(&mut cursor)? {
+                        0 => None,
+                        len => {
+                            let len = usize::try_from(len - 1)?;
+                            if len > cursor.len() {
+                                bail!("symlink target length exceeds directory block size");
+                            }
+                            let (target, rest) = cursor.split_at(len);
+                            cursor = rest;
+                            Some(target.to_vec())
+                        }
+                    };
+                    let attributes = EntryAttributes {
+                        target,
+                        ..Default::default()
+                    };
+                    callback(etype, name, 0, 0, 0, attributes)?
+                }
+                _ => callback(etype, name, 0, 0, 0, EntryAttributes::default())?,
             };
             if !cont {
                 return Ok(());
@@ -327,7 +459,7 @@ impl DirInfo {
 /// A Catalogs simply contains list of files and directories
 /// (directory tree). They are use to find content without having to
 /// search the real archive (which may be large). For files, they
-/// include the last modification time and file size.
+/// include the last modification time, file size, mode and ownership.
 pub struct CatalogWriter<W> {
     writer: W,
     dirstack: Vec<DirInfo>,
@@ -336,13 +468,16 @@ pub struct CatalogWriter<W> {
 
 impl<W: Write> CatalogWriter<W> {
     /// Create a new  CatalogWriter instance
+    ///
+    /// Catalogs are written in format version 1.1. Readers from before that version, like older
+    /// servers or file-restore, reject them with an unexpected magic number error.
     pub fn new(writer: W) -> Result<Self, Error> {
         let mut me = Self {
             writer,
             dirstack: vec![DirInfo::new_rootdir()],
             pos: 0,
         };
-        me.write_all(&PROXMOX_CATALOG_FILE_MAGIC_1_0)?;
+        me.write_all(&PROXMOX_CATALOG_FILE_MAGIC_1_1)?;
         Ok(me)
     }
 
@@ -374,6 +509,16 @@ impl<W: Write> CatalogWriter<W> {
     }
 }
 
+impl CatalogWriter<std::fs::File> {
+    /// Like [`CatalogWriter::finish`], but also fsyncs the file, so that the catalog is complete
+    /// on disk when this returns.
+    pub fn finish_and_sync(&mut self) -> Result<(), Error> {
+        self.finish()?;
+        self.writer.sync_all()?;
+        Ok(())
+    }
+}
+
 impl<W: Write> BackupCatalogWriter for CatalogWriter<W> {
     fn start_directory(&mut self, name: &CStr) -> Result<(), Error> {
         let new = DirInfo::new(name.to_owned());
@@ -407,7 +552,15 @@ impl<W: Write> BackupCatalogWriter for CatalogWriter<W> {
         Ok(())
     }
 
-    fn add_file(&mut self, name: &CStr, size: u64, mtime: i64) -> Result<(), Error> {
+    fn add_file(
+        &mut self,
+        name: &CStr,
+        size: u64,
+        mtime: i64,
+        mode: u32,
+        uid: u32,
+        gid: u32,
+    ) -> Result<(), Error> {
         let dir = self
             .dirstack
             .last_mut()
@@ -415,7 +568,43 @@ impl<W: Write> BackupCatalogWriter for CatalogWriter<W> {
         let name = name.to_bytes().to_vec();
         dir.entries.push(DirEntry {
             name,
-            attr: DirEntryAttribute::File { size, mtime },
+            attr: DirEntryAttribute::File {
+                size,
+                mtime,
+                mode: Some(mode),
+                uid: Some(uid),
+                gid: Some(gid),
+                offset: None,
+            },
+        });
+        Ok(())
+    }
+
+    fn add_file_with_offset(
+        &mut self,
+        name: &CStr,
+        size: u64,
+        mtime: i64,
+        mode: u32,
+        uid: u32,
+        gid: u32,
+        offset: u64,
+    ) -> Result<(), Error> {
+        let dir = self
+            .dirstack
+            .last_mut()
+            .ok_or_else(|| format_err!("outside root"))?;
+        let name = name.to_bytes().to_vec();
+        dir.entries.push(DirEntry {
+            name,
+            attr: DirEntryAttribute::File {
+                size,
+                mtime,
+                mode: Some(mode),
+                uid: Some(uid),
+                gid: Some(gid),
+                offset: Some(offset),
+            },
         });
         Ok(())
     }
@@ -428,7 +617,22 @@ impl<W: Write> BackupCatalogWriter for CatalogWriter<W> {
         let name = name.to_bytes().to_vec();
         dir.entries.push(DirEntry {
             name,
-            attr: DirEntryAttribute::Symlink,
+            attr: DirEntryAttribute::Symlink { target: None },
+        });
+        Ok(())
+    }
+
+    fn add_symlink_with_target(&mut self, name: &CStr, target: &CStr) -> Result<(), Error> {
+        let dir = self
+            .dirstack
+            .last_mut()
+            .ok_or_else(|| format_err!("outside root"))?;
+        let name = name.to_bytes().to_vec();
+        dir.entries.push(DirEntry {
+            name,
+            attr: DirEntryAttribute::Symlink {
+                target: Some(target.to_bytes().to_vec()),
+            },
         });
         Ok(())
     }
@@ -502,12 +706,48 @@ impl<W: Write> BackupCatalogWriter for CatalogWriter<W> {
 /// Read Catalog files
 pub struct CatalogReader<R> {
     reader: R,
+    version: Option<CatalogVersion>,
+}
+
+impl CatalogReader<std::io::Cursor<Vec<u8>>> {
+    /// Create a new CatalogReader instance from a forward-only reader
+    ///
+    /// The whole catalog is read into memory, so this should only be used when the
+    /// underlying reader does not support seeking.
+    pub fn from_reader_buffered<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        if data.len() < PROXMOX_CATALOG_FILE_MAGIC_1_0.len() + 8 {
+            bail!("catalog too small ({} bytes)", data.len());
+        }
+        Ok(Self::new(std::io::Cursor::new(data)))
+    }
 }
 
 impl<R: Read + Seek> CatalogReader<R> {
     /// Create a new CatalogReader instance
     pub fn new(reader: R) -> Self {
-        Self { reader }
+        Self {
+            reader,
+            version: None,
+        }
+    }
+
+    /// Check the catalog magic and return the format version
+    fn version(&mut self) -> Result<CatalogVersion, Error> {
+        if let Some(version) = self.version {
+            return Ok(version);
+        }
+        self.reader.seek(SeekFrom::Start(0))?;
+        let mut magic = [0u8; 8];
+        self.reader.read_exact(&mut magic)?;
+        let version = match magic {
+            PROXMOX_CATALOG_FILE_MAGIC_1_0 => CatalogVersion::V1_0,
+            PROXMOX_CATALOG_FILE_MAGIC_1_1 => CatalogVersion::V1_1,
+            _ => bail!("got unexpected magic number for catalog"),
+        };
+        self.version = Some(version);
+        Ok(version)
     }
 
     /// Print whole catalog to stdout
@@ -525,12 +765,7 @@ impl<R: Read + Seek> CatalogReader<R> {
     /// Get the root DirEntry
     pub fn root(&mut self) -> Result<DirEntry, Error> {
         // Root dir is special
-        self.reader.seek(SeekFrom::Start(0))?;
-        let mut magic = [0u8; 8];
-        self.reader.read_exact(&mut magic)?;
-        if magic != PROXMOX_CATALOG_FILE_MAGIC_1_0 {
-            bail!("got unexpected magic number for catalog");
-        }
+        self.version()?;
         self.reader.seek(SeekFrom::End(-8))?;
         let start = unsafe { self.reader.read_le_value::<u64>()? };
         Ok(DirEntry {
@@ -547,11 +782,15 @@ impl<R: Read + Seek> CatalogReader<R> {
         };
 
         let data = self.read_raw_dirinfo_block(start)?;
+        let version = self.version()?;
 
         let mut entry_list = Vec::new();
 
-        DirInfo::parse(&data, |etype, name, offset, size, mtime| {
-            let entry = DirEntry::new(etype, name.to_vec(), start - offset, size, mtime);
+        DirInfo::parse(&data, version, |etype, name, offset, size, mtime, attrs| {
+            if offset > start {
+                bail!("got wrong directory offset ({} > {})", offset, start);
+            }
+            let entry = DirEntry::new(etype, name.to_vec(), start - offset, size, mtime, attrs);
             entry_list.push(entry);
             Ok(true)
         })?;
@@ -598,14 +837,18 @@ impl<R: Read + Seek> CatalogReader<R> {
         };
 
         let data = self.read_raw_dirinfo_block(start)?;
+        let version = self.version()?;
 
         let mut item = None;
-        DirInfo::parse(&data, |etype, name, offset, size, mtime| {
+        DirInfo::parse(&data, version, |etype, name, offset, size, mtime, attrs| {
             if name != filename {
                 return Ok(true);
             }
+            if offset > start {
+                bail!("got wrong directory offset ({} > {})", offset, start);
+            }
 
-            let entry = DirEntry::new(etype, name.to_vec(), start - offset, size, mtime);
+            let entry = DirEntry::new(etype, name.to_vec(), start - offset, size, mtime, attrs);
             item = Some(entry);
             Ok(false) // stop parsing
         })?;
@@ -613,6 +856,63 @@ impl<R: Read + Seek> CatalogReader<R> {
         Ok(item)
     }
 
+    /// Lookup a DirEntry inside a parent directory, ignoring the case of ASCII letters
+    ///
+    /// Only ASCII letters are compared case-insensitively, all other bytes (including UTF-8
+    /// multi-byte sequences) have to match exactly. If several entries match, the first one in
+    /// catalog order is returned, use `lookup_ci_all` to get all of them.
+    pub fn lookup_ci(
+        &mut self,
+        parent: &DirEntry,
+        filename: &[u8],
+    ) -> Result<Option<DirEntry>, Error> {
+        Ok(self
+            .lookup_ci_matches(parent, filename, true)?
+            .into_iter()
+            .next())
+    }
+
+    /// Lookup all DirEntries inside a parent directory matching `filename`, ignoring the case
+    /// of ASCII letters (see `lookup_ci`)
+    pub fn lookup_ci_all(
+        &mut self,
+        parent: &DirEntry,
+        filename: &[u8],
+    ) -> Result<Vec<DirEntry>, Error> {
+        self.lookup_ci_matches(parent, filename, false)
+    }
+
+    fn lookup_ci_matches(
+        &mut self,
+        parent: &DirEntry,
+        filename: &[u8],
+        first_only: bool,
+    ) -> Result<Vec<DirEntry>, Error> {
+        let start = match parent.attr {
+            DirEntryAttribute::Directory { start } => start,
+            _ => bail!("parent is not a directory - internal error"),
+        };
+
+        let data = self.read_raw_dirinfo_block(start)?;
+        let version = self.version()?;
+
+        let mut items = Vec::new();
+        DirInfo::parse(&data, version, |etype, name, offset, size, mtime, attrs| {
+            if !name.eq_ignore_ascii_case(filename) {
+                return Ok(true);
+            }
+            if offset > start {
+                bail!("got wrong directory offset ({} > {})", offset, start);
+            }
+
+            let entry = DirEntry::new(etype, name.to_vec(), start - offset, size, mtime, attrs);
+            items.push(entry);
+            Ok(!first_only)
+        })?;
+
+        Ok(items)
+    }
+
     /// Read the raw directory info block from current reader position.
     fn read_raw_dirinfo_block(&mut self, start: u64) -> Result<Vec<u8>, Error> {
         self.reader.seek(SeekFrom::Start(start))?;
@@ -627,8 +927,9 @@ impl<R: Read + Seek> CatalogReader<R> {
     /// Print the content of a directory to stdout
     pub fn dump_dir(&mut self, prefix: &std::path::Path, start: u64) -> Result<(), Error> {
         let data = self.read_raw_dirinfo_block(start)?;
+        let version = self.version()?;
 
-        DirInfo::parse(&data, |etype, name, offset, size, mtime| {
+        DirInfo::parse(&data, version, |etype, name, offset, size, mtime, attrs| {
             let mut path = std::path::PathBuf::from(prefix);
             let name: &OsStr = OsStrExt::from_bytes(name);
             path.push(name);
@@ -648,8 +949,32 @@ impl<R: Read + Seek> CatalogReader<R> {
                         mtime_string = s;
                     }
 
-                    log::info!("{} {:?} {} {}", etype, path, size, mtime_string,);
+                    match attrs {
+                        EntryAttributes {
+                            mode: Some(mode),
+                            uid: Some(uid),
+                            gid: Some(gid),
+                            ..
+                        } => log::info!(
+                            "{} {:?} {} {} {:o} {}:{}",
+                            etype,
+                            path,
+                            size,
+                            mtime_string,
+                            mode & 0o7777,
+                            uid,
+                            gid,
+                        ),
+                        _ => log::info!("{} {:?} {} {}", etype, path, size, mtime_string,),
+                    }
                 }
+                CatalogEntryType::Symlink => match attrs.target {
+                    Some(target) => {
+                        let target: &OsStr = OsStrExt::from_bytes(&target);
+                        log::info!("{} {:?} -> {:?}", etype, path, target);
+                    }
+                    None => log::info!("{} {:?}", etype, path),
+                },
                 _ => {
                     log::info!("{} {:?}", etype, path);
                 }
@@ -691,6 +1016,110 @@ impl<R: Read + Seek> CatalogReader<R> {
         Ok(())
     }
 
+    /// Search the whole catalog for entries matching a shell-style glob pattern.
+    ///
+    /// Patterns starting with a `/` are anchored at the archive root, all others match at any
+    /// directory level. At most `limit` paths are returned, if set.
+    pub fn search(&mut self, pattern: &str, limit: Option<usize>) -> Result<Vec<PathBuf>, Error> {
+        let pattern =
+            MatchEntry::parse_pattern(pattern, PatternFlag::PATH_NAME, MatchType::Include)?;
+        let root = self.root()?;
+
+        let mut result = Vec::new();
+        self.search_dir(&root, &mut Vec::new(), &[&pattern], limit, &mut result)?;
+
+        Ok(result)
+    }
+
+    /// Like [`CatalogReader::find`], but collects the matching paths into `result` and stops
+    /// walking the catalog once `limit` paths are found.
+    ///
+    /// Returns true if the limit was reached.
+    fn search_dir<'a>(
+        &mut self,
+        parent: &DirEntry,
+        file_path: &mut Vec<u8>,
+        match_list: &'a impl MatchList<'a>,
+        limit: Option<usize>,
+        result: &mut Vec<PathBuf>,
+    ) -> Result<bool, Error> {
+        let limit_reached =
+            |result: &Vec<PathBuf>| matches!(limit, Some(limit) if result.len() >= limit);
+
+        if limit_reached(result) {
+            return Ok(true);
+        }
+
+        let file_len = file_path.len();
+        for e in self.read_dir(parent)? {
+            let is_dir = e.is_directory();
+            file_path.truncate(file_len);
+            if !e.name.starts_with(b"/") {
+                file_path.reserve(e.name.len() + 1);
+                file_path.push(b'/');
+            }
+            file_path.extend(&e.name);
+            match match_list.matches(&file_path, e.get_file_mode()) {
+                Ok(Some(MatchType::Exclude)) => continue,
+                Ok(Some(MatchType::Include)) => {
+                    result.push(PathBuf::from(OsStr::from_bytes(file_path)));
+                    if limit_reached(result) {
+                        return Ok(true);
+                    }
+                }
+                _ => (),
+            }
+            if is_dir && self.search_dir(&e, file_path, match_list, limit, result)? {
+                return Ok(true);
+            }
+        }
+        file_path.truncate(file_len);
+
+        Ok(false)
+    }
+
+    /// Compute summary statistics by walking the whole catalog
+    pub fn stats(&mut self) -> Result<CatalogStats, Error> {
+        let root = self.root()?;
+        let mut stats = CatalogStats::default();
+        self.stats_dir(&root, &mut stats)?;
+        Ok(stats)
+    }
+
+    fn stats_dir(&mut self, parent: &DirEntry, stats: &mut CatalogStats) -> Result<(), Error> {
+        let parent_start = match parent.attr {
+            DirEntryAttribute::Directory { start } => start,
+            _ => bail!("parent is not a directory - internal error"),
+        };
+
+        for entry in self.read_dir(parent)? {
+            match entry.attr {
+                DirEntryAttribute::Directory { start } => {
+                    // directories are always written before their parent
+                    if start >= parent_start {
+                        bail!(
+                            "got invalid directory start offset for {:?} ({} >= {})",
+                            String::from_utf8_lossy(&entry.name),
+                            start,
+                            parent_start,
+                        );
+                    }
+                    stats.directories += 1;
+                    self.stats_dir(&entry, stats)?;
+                }
+                DirEntryAttribute::File { size, mtime, .. } => {
+                    stats.files += 1;
+                    stats.total_size += size;
+                    stats.oldest_mtime = Some(stats.oldest_mtime.map_or(mtime, |m| m.min(mtime)));
+                    stats.newest_mtime = Some(stats.newest_mtime.map_or(mtime, |m| m.max(mtime)));
+                }
+                _ => stats.other += 1,
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns the list of content of the given path
     pub fn list_dir_contents(&mut self, path: &[u8]) -> Result<Vec<ArchiveEntry>, Error> {
         let dir = self.lookup_recursive(path)?;
@@ -705,7 +1134,7 @@ impl<R: Read + Seek> CatalogReader<R> {
             components.push(b'/');
             components.extend(&direntry.name);
             let mut entry = ArchiveEntry::new(&components, Some(&direntry.attr));
-            if let DirEntryAttribute::File { size, mtime } = direntry.attr {
+            if let DirEntryAttribute::File { size, mtime, .. } = direntry.attr {
                 entry.size = size.into();
                 entry.mtime = mtime.into();
             }
@@ -716,6 +1145,205 @@ impl<R: Read + Seek> CatalogReader<R> {
     }
 }
 
+/// Summary statistics of a catalog.
+#[api]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CatalogStats {
+    /// Number of regular files
+    pub files: u64,
+    /// Number of directories, not counting the root directory
+    pub directories: u64,
+    /// Number of other entries (symlinks, hardlinks, devices, fifos and sockets)
+    pub other: u64,
+    /// Sum of all regular file sizes
+    pub total_size: u64,
+    /// Oldest file modification time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_mtime: Option<i64>,
+    /// Newest file modification time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest_mtime: Option<i64>,
+}
+
+/// An entry in a hierarchy of files for restore and listing.
+#[api]
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Base64-encoded full path to the file, including the filename
+    pub filepath: String,
+    /// Displayable filename text for UIs
+    pub text: String,
+    /// File or directory type of this entry
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    /// Is this entry a leaf node, or does it have children (i.e. a directory)?
+    pub leaf: bool,
+    /// The file size, if entry_type is 'f' (file)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// The file "last modified" time stamp, if entry_type is 'f' (file)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<i64>,
+}
+
+impl ArchiveEntry {
+    pub fn new(filepath: &[u8], entry_type: Option<&DirEntryAttribute>) -> Self {
+        let size = match entry_type {
+            Some(DirEntryAttribute::File { size, .. }) => Some(*size),
+            _ => None,
+        };
+        Self::new_with_size(filepath, entry_type, size)
+    }
+
+    pub fn new_with_size(
+        filepath: &[u8],
+        entry_type: Option<&DirEntryAttribute>,
+        size: Option<u64>,
+    ) -> Self {
+        Self {
+            filepath: base64::encode(filepath),
+            text: String::from_utf8_lossy(filepath.split(|x| *x == b'/').last().unwrap())
+                .to_string(),
+            entry_type: match entry_type {
+                Some(entry_type) => CatalogEntryType::from(entry_type).to_string(),
+                None => "v".to_owned(),
+            },
+            leaf: !matches!(entry_type, None | Some(DirEntryAttribute::Directory { .. })),
+            size,
+            mtime: match entry_type {
+                Some(DirEntryAttribute::File { mtime, .. }) => Some(*mtime),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// A single difference between two catalogs, as returned by [`catalog_diff`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CatalogDiffEntry {
+    /// Entry only exists in the new catalog
+    Added {
+        path: Vec<u8>,
+        attr: DirEntryAttribute,
+    },
+    /// Entry only exists in the old catalog
+    Removed {
+        path: Vec<u8>,
+        attr: DirEntryAttribute,
+    },
+    /// Entry exists in both catalogs, but its type, size or mtime changed
+    Modified {
+        path: Vec<u8>,
+        old: DirEntryAttribute,
+        new: DirEntryAttribute,
+    },
+}
+
+/// Compare two catalogs and return the list of added, removed and modified entries
+///
+/// Directories present in both catalogs are compared recursively. A directory which only exists
+/// on one side is reported as a single entry, without listing its contents.
+pub fn catalog_diff<R: Read + Seek>(
+    old: &mut CatalogReader<R>,
+    new: &mut CatalogReader<R>,
+) -> Result<Vec<CatalogDiffEntry>, Error> {
+    let old_root = old.root()?;
+    let new_root = new.root()?;
+
+    let mut result = Vec::new();
+    catalog_diff_dir(old, &old_root, new, &new_root, &mut Vec::new(), &mut result)?;
+
+    Ok(result)
+}
+
+fn catalog_diff_dir<R: Read + Seek>(
+    old: &mut CatalogReader<R>,
+    old_dir: &DirEntry,
+    new: &mut CatalogReader<R>,
+    new_dir: &DirEntry,
+    prefix: &mut Vec<u8>,
+    result: &mut Vec<CatalogDiffEntry>,
+) -> Result<(), Error> {
+    let mut old_entries = old.read_dir(old_dir)?;
+    let mut new_entries = new.read_dir(new_dir)?;
+    old_entries.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    new_entries.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+    let prefix_len = prefix.len();
+    let mut old_iter = old_entries.into_iter().peekable();
+    let mut new_iter = new_entries.into_iter().peekable();
+
+    loop {
+        let ordering = match (old_iter.peek(), new_iter.peek()) {
+            (None, None) => break,
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (Some(o), Some(n)) => o.name.cmp(&n.name),
+        };
+
+        prefix.truncate(prefix_len);
+        prefix.push(b'/');
+
+        match ordering {
+            std::cmp::Ordering::Less => {
+                let entry = old_iter.next().unwrap();
+                prefix.extend(&entry.name);
+                result.push(CatalogDiffEntry::Removed {
+                    path: prefix.clone(),
+                    attr: entry.attr,
+                });
+            }
+            std::cmp::Ordering::Greater => {
+                let entry = new_iter.next().unwrap();
+                prefix.extend(&entry.name);
+                result.push(CatalogDiffEntry::Added {
+                    path: prefix.clone(),
+                    attr: entry.attr,
+                });
+            }
+            std::cmp::Ordering::Equal => {
+                let old_entry = old_iter.next().unwrap();
+                let new_entry = new_iter.next().unwrap();
+                prefix.extend(&old_entry.name);
+
+                let modified = match (&old_entry.attr, &new_entry.attr) {
+                    (DirEntryAttribute::Directory { .. }, DirEntryAttribute::Directory { .. }) => {
+                        catalog_diff_dir(old, &old_entry, new, &new_entry, prefix, result)?;
+                        false
+                    }
+                    (
+                        DirEntryAttribute::File {
+                            size: old_size,
+                            mtime: old_mtime,
+                            ..
+                        },
+                        DirEntryAttribute::File {
+                            size: new_size,
+                            mtime: new_mtime,
+                            ..
+                        },
+                    ) => old_size != new_size || old_mtime != new_mtime,
+                    (old_attr, new_attr) => {
+                        CatalogEntryType::from(old_attr) != CatalogEntryType::from(new_attr)
+                    }
+                };
+
+                if modified {
+                    result.push(CatalogDiffEntry::Modified {
+                        path: prefix.clone(),
+                        old: old_entry.attr,
+                        new: new_entry.attr,
+                    });
+                }
+            }
+        }
+    }
+    prefix.truncate(prefix_len);
+
+    Ok(())
+}
+
 /// Serialize i64 as short, variable length byte sequence
 ///
 /// Stores 7 bits per byte, Bit 8 indicates the end of the sequence (when not set).
@@ -803,10 +1431,25 @@ pub fn catalog_encode_u64<W: Write>(writer: &mut W, v: u64) -> Result<(), Error>
     Ok(())
 }
 
+/// Serialize an optional u32 using the u64 encoding
+///
+/// `None` is stored as zero, all other values are shifted by one.
+fn catalog_encode_optional_u32<W: Write>(writer: &mut W, v: Option<u32>) -> Result<(), Error> {
+    catalog_encode_u64(writer, v.map(|v| v as u64 + 1).unwrap_or(0))
+}
+
+/// Deserialize an optional u32 written by `catalog_encode_optional_u32`
+fn catalog_decode_optional_u32<R: Read>(reader: &mut R) -> Result<Option<u32>, Error> {
+    match catalog_decode_u64(reader)? {
+        0 => Ok(None),
+        v => Ok(Some(u32::try_from(v - 1)?)),
+    }
+}
+
 /// Deserialize u64 from variable length byte sequence
 ///
-/// We currently read maximal 10 bytes, which give a maximum of 70 bits,
-/// but we currently only encode up to 64 bits
+/// We read maximal 10 bytes, which give a maximum of 70 bits. Since we only
+/// encode up to 64 bits, the last byte may only contain the highest bit.
 pub fn catalog_decode_u64<R: Read>(reader: &mut R) -> Result<u64, Error> {
     let mut v: u64 = 0;
     let mut buf = [0u8];
@@ -818,6 +1461,9 @@ pub fn catalog_decode_u64<R: Read>(reader: &mut R) -> Result<u64, Error> {
         }
         reader.read_exact(&mut buf)?;
         let t = buf[0];
+        if i == 9 && t > 1 {
+            bail!("decode_u64 failed - value exceeds 64 bits");
+        }
         if t < 128 {
             v |= (t as u64) << (i * 7);
             return Ok(v);
@@ -850,7 +1496,14 @@ fn test_catalog_u64_encoder() {
     test_encode_decode((1 << 12) - 1);
     test_encode_decode((1 << 20) - 1);
     test_encode_decode((1 << 50) - 1);
+    test_encode_decode((1 << 63) - 1);
+    test_encode_decode(1 << 63);
+    test_encode_decode((1 << 63) + 1);
     test_encode_decode(u64::MAX);
+
+    // values above 64 bits must not silently overflow
+    let data = [255u8, 255, 255, 255, 255, 255, 255, 255, 255, 3];
+    assert!(catalog_decode_u64(&mut &data[..]).is_err());
 }
 
 #[test]
@@ -897,55 +1550,446 @@ fn test_catalog_i64_compatibility() {
     test_encode_decode(u64::MAX);
 }
 
-/// An entry in a hierarchy of files for restore and listing.
-#[api]
-#[derive(Serialize, Deserialize)]
-pub struct ArchiveEntry {
-    /// Base64-encoded full path to the file, including the filename
-    pub filepath: String,
-    /// Displayable filename text for UIs
-    pub text: String,
-    /// File or directory type of this entry
-    #[serde(rename = "type")]
-    pub entry_type: String,
-    /// Is this entry a leaf node, or does it have children (i.e. a directory)?
-    pub leaf: bool,
-    /// The file size, if entry_type is 'f' (file)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub size: Option<u64>,
-    /// The file "last modified" time stamp, if entry_type is 'f' (file)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub mtime: Option<i64>,
+#[test]
+fn test_catalog_file_attributes() {
+    use std::io::Cursor;
+
+    let mut writer = CatalogWriter::new(Vec::new()).unwrap();
+    writer
+        .start_directory(&CString::new("dir").unwrap())
+        .unwrap();
+    writer
+        .add_file(
+            &CString::new("file").unwrap(),
+            1024,
+            1_600_000_000,
+            0o100644,
+            1000,
+            100,
+        )
+        .unwrap();
+    writer.end_directory().unwrap();
+    writer
+        .add_file(&CString::new("root-file").unwrap(), 0, -1, 0o100600, 0, 0)
+        .unwrap();
+    writer.finish().unwrap();
+
+    let mut reader = CatalogReader::new(Cursor::new(writer.writer));
+    let file = reader.lookup_recursive(b"/dir/file").unwrap();
+    assert_eq!(
+        file.attr,
+        DirEntryAttribute::File {
+            size: 1024,
+            mtime: 1_600_000_000,
+            mode: Some(0o100644),
+            uid: Some(1000),
+            gid: Some(100),
+            offset: None,
+        }
+    );
+    let file = reader.lookup_recursive(b"/root-file").unwrap();
+    assert_eq!(
+        file.attr,
+        DirEntryAttribute::File {
+            size: 0,
+            mtime: -1,
+            mode: Some(0o100600),
+            uid: Some(0),
+            gid: Some(0),
+            offset: None,
+        }
+    );
 }
 
-impl ArchiveEntry {
-    pub fn new(filepath: &[u8], entry_type: Option<&DirEntryAttribute>) -> Self {
-        let size = match entry_type {
-            Some(DirEntryAttribute::File { size, .. }) => Some(*size),
-            _ => None,
-        };
-        Self::new_with_size(filepath, entry_type, size)
-    }
+#[test]
+fn test_catalog_format_1_0_compatibility() {
+    use std::io::Cursor;
+
+    // hand-craft a version 1.0 catalog with a single file in the root directory
+    let mut table = Vec::new();
+    catalog_encode_u64(&mut table, 1).unwrap();
+    table.push(CatalogEntryType::File as u8);
+    catalog_encode_u64(&mut table, 4).unwrap();
+    table.extend_from_slice(b"file");
+    catalog_encode_u64(&mut table, 42).unwrap();
+    catalog_encode_i64(&mut table, 1_600_000_000).unwrap();
+
+    let mut data = PROXMOX_CATALOG_FILE_MAGIC_1_0.to_vec();
+    let start = data.len() as u64;
+    catalog_encode_u64(&mut data, table.len() as u64).unwrap();
+    data.extend_from_slice(&table);
+    data.extend_from_slice(&start.to_le_bytes());
+
+    let mut reader = CatalogReader::new(Cursor::new(data));
+    let root = reader.root().unwrap();
+    let entries = reader.read_dir(&root).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].name, b"file");
+    assert_eq!(
+        entries[0].attr,
+        DirEntryAttribute::File {
+            size: 42,
+            mtime: 1_600_000_000,
+            mode: None,
+            uid: None,
+            gid: None,
+            offset: None,
+        }
+    );
+}
 
-    pub fn new_with_size(
-        filepath: &[u8],
-        entry_type: Option<&DirEntryAttribute>,
-        size: Option<u64>,
-    ) -> Self {
-        Self {
-            filepath: base64::encode(filepath),
-            text: String::from_utf8_lossy(filepath.split(|x| *x == b'/').last().unwrap())
-                .to_string(),
-            entry_type: match entry_type {
-                Some(entry_type) => CatalogEntryType::from(entry_type).to_string(),
-                None => "v".to_owned(),
-            },
-            leaf: !matches!(entry_type, None | Some(DirEntryAttribute::Directory { .. })),
-            size,
-            mtime: match entry_type {
-                Some(DirEntryAttribute::File { mtime, .. }) => Some(*mtime),
-                _ => None,
-            },
+#[test]
+fn test_catalog_symlink_target() {
+    use std::io::Cursor;
+
+    let mut writer = CatalogWriter::new(Vec::new()).unwrap();
+    writer
+        .add_symlink_with_target(
+            &CString::new("link").unwrap(),
+            &CString::new("../target/file").unwrap(),
+        )
+        .unwrap();
+    writer
+        .add_symlink(&CString::new("no-target").unwrap())
+        .unwrap();
+    writer.finish().unwrap();
+
+    let mut reader = CatalogReader::new(Cursor::new(writer.writer));
+    let link = reader.lookup_recursive(b"/link").unwrap();
+    assert_eq!(
+        link.attr,
+        DirEntryAttribute::Symlink {
+            target: Some(b"../target/file".to_vec())
+        }
+    );
+    assert!(link.is_symlink());
+    let link = reader.lookup_recursive(b"/no-target").unwrap();
+    assert_eq!(link.attr, DirEntryAttribute::Symlink { target: None });
+}
+
+#[test]
+fn test_catalog_file_offset() {
+    use std::io::Cursor;
+
+    let mut writer = CatalogWriter::new(Vec::new()).unwrap();
+    writer
+        .add_file_with_offset(&CString::new("file").unwrap(), 42, 0, 0o100644, 0, 0, 0)
+        .unwrap();
+    writer
+        .add_file_with_offset(
+            &CString::new("other").unwrap(),
+            1,
+            0,
+            0o100644,
+            0,
+            0,
+            1 << 40,
+        )
+        .unwrap();
+    writer.finish().unwrap();
+
+    let mut reader = CatalogReader::new(Cursor::new(writer.writer));
+    let file = reader.lookup_recursive(b"/file").unwrap();
+    assert!(matches!(
+        file.attr,
+        DirEntryAttribute::File {
+            size: 42,
+            offset: Some(0),
+            ..
+        }
+    ));
+    let file = reader.lookup_recursive(b"/other").unwrap();
+    assert!(matches!(
+        file.attr,
+        DirEntryAttribute::File {
+            offset: Some(offset),
+            ..
+        } if offset == 1 << 40
+    ));
+}
+
+#[test]
+fn test_catalog_reader_buffered() {
+    // only implements Read, like a network stream
+    struct ForwardReader<'a>(&'a [u8]);
+
+    impl Read for ForwardReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
         }
     }
+
+    let mut writer = CatalogWriter::new(Vec::new()).unwrap();
+    writer
+        .start_directory(&CString::new("dir").unwrap())
+        .unwrap();
+    writer
+        .add_file(&CString::new("file").unwrap(), 42, 0, 0o100644, 0, 0)
+        .unwrap();
+    writer.end_directory().unwrap();
+    writer.finish().unwrap();
+
+    let mut reader = CatalogReader::from_reader_buffered(ForwardReader(&writer.writer)).unwrap();
+    let root = reader.root().unwrap();
+    let entries = reader.read_dir(&root).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].is_directory());
+    let file = reader.lookup(&entries[0], b"file").unwrap().unwrap();
+    assert!(matches!(
+        file.attr,
+        DirEntryAttribute::File { size: 42, .. }
+    ));
+    reader.dump().unwrap();
+
+    assert!(CatalogReader::from_reader_buffered(ForwardReader(b"short")).is_err());
+}
+
+#[test]
+fn test_catalog_search() {
+    use std::io::Cursor;
+
+    let mut writer = CatalogWriter::new(Vec::new()).unwrap();
+    writer
+        .start_directory(&CString::new("etc").unwrap())
+        .unwrap();
+    writer
+        .add_file(&CString::new("hosts").unwrap(), 10, 0, 0o100644, 0, 0)
+        .unwrap();
+    writer
+        .start_directory(&CString::new("apt").unwrap())
+        .unwrap();
+    writer
+        .add_file(
+            &CString::new("sources.list").unwrap(),
+            20,
+            0,
+            0o100644,
+            0,
+            0,
+        )
+        .unwrap();
+    writer
+        .add_file(&CString::new("hosts").unwrap(), 30, 0, 0o100644, 0, 0)
+        .unwrap();
+    writer.end_directory().unwrap();
+    writer.end_directory().unwrap();
+    writer
+        .add_file(&CString::new("hosts").unwrap(), 40, 0, 0o100644, 0, 0)
+        .unwrap();
+    writer.finish().unwrap();
+
+    let mut reader = CatalogReader::new(Cursor::new(writer.writer));
+
+    let mut found = reader.search("hosts", None).unwrap();
+    found.sort();
+    assert_eq!(
+        found,
+        vec![
+            PathBuf::from("/etc/apt/hosts"),
+            PathBuf::from("/etc/hosts"),
+            PathBuf::from("/hosts"),
+        ]
+    );
+
+    let found = reader.search("/etc/hosts", None).unwrap();
+    assert_eq!(found, vec![PathBuf::from("/etc/hosts")]);
+
+    let found = reader.search("*.list", None).unwrap();
+    assert_eq!(found, vec![PathBuf::from("/etc/apt/sources.list")]);
+
+    let found = reader.search("/etc/*", None).unwrap();
+    assert_eq!(found.len(), 2);
+
+    let found = reader.search("hosts", Some(2)).unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(reader.search("hosts", Some(1)).unwrap().len(), 1);
+    assert!(reader.search("hosts", Some(0)).unwrap().is_empty());
+
+    assert!(reader.search("nonexistent", None).unwrap().is_empty());
+}
+
+#[test]
+fn test_catalog_diff() {
+    use std::io::Cursor;
+
+    fn file(writer: &mut CatalogWriter<Vec<u8>>, name: &str, size: u64, mtime: i64) {
+        writer
+            .add_file(&CString::new(name).unwrap(), size, mtime, 0o100644, 0, 0)
+            .unwrap();
+    }
+
+    let mut writer = CatalogWriter::new(Vec::new()).unwrap();
+    file(&mut writer, "unchanged", 1, 0);
+    file(&mut writer, "grown", 1, 0);
+    file(&mut writer, "touched", 1, 0);
+    file(&mut writer, "removed", 1, 0);
+    file(&mut writer, "type-changed", 1, 0);
+    writer
+        .start_directory(&CString::new("gone").unwrap())
+        .unwrap();
+    file(&mut writer, "inner", 1, 0);
+    writer.end_directory().unwrap();
+    writer
+        .start_directory(&CString::new("common").unwrap())
+        .unwrap();
+    file(&mut writer, "a", 1, 0);
+    writer.end_directory().unwrap();
+    writer.finish().unwrap();
+    let mut old = CatalogReader::new(Cursor::new(writer.writer));
+
+    let mut writer = CatalogWriter::new(Vec::new()).unwrap();
+    file(&mut writer, "unchanged", 1, 0);
+    file(&mut writer, "grown", 2, 0);
+    file(&mut writer, "touched", 1, 1);
+    file(&mut writer, "added", 1, 0);
+    writer
+        .add_symlink(&CString::new("type-changed").unwrap())
+        .unwrap();
+    writer
+        .start_directory(&CString::new("new-dir").unwrap())
+        .unwrap();
+    file(&mut writer, "inner", 1, 0);
+    writer.end_directory().unwrap();
+    writer
+        .start_directory(&CString::new("common").unwrap())
+        .unwrap();
+    file(&mut writer, "a", 1, 0);
+    file(&mut writer, "b", 1, 0);
+    writer.end_directory().unwrap();
+    writer.finish().unwrap();
+    let mut new = CatalogReader::new(Cursor::new(writer.writer));
+
+    let diff = catalog_diff(&mut old, &mut new).unwrap();
+    let summary: Vec<(char, String)> = diff
+        .iter()
+        .map(|entry| {
+            let (kind, path) = match entry {
+                CatalogDiffEntry::Added { path, .. } => ('+', path),
+                CatalogDiffEntry::Removed { path, .. } => ('-', path),
+                CatalogDiffEntry::Modified { path, .. } => ('M', path),
+            };
+            (kind, String::from_utf8_lossy(path).into_owned())
+        })
+        .collect();
+
+    assert_eq!(
+        summary,
+        vec![
+            ('+', "/added".to_string()),
+            ('+', "/common/b".to_string()),
+            ('-', "/gone".to_string()),
+            ('M', "/grown".to_string()),
+            ('+', "/new-dir".to_string()),
+            ('-', "/removed".to_string()),
+            ('M', "/touched".to_string()),
+            ('M', "/type-changed".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_catalog_stats() {
+    use std::io::Cursor;
+
+    let mut writer = CatalogWriter::new(Vec::new()).unwrap();
+    writer.start_directory(&CString::new("a").unwrap()).unwrap();
+    writer
+        .add_file(&CString::new("f1").unwrap(), 100, 10, 0o100644, 0, 0)
+        .unwrap();
+    writer.start_directory(&CString::new("b").unwrap()).unwrap();
+    writer
+        .add_file(&CString::new("f2").unwrap(), 200, -5, 0o100644, 0, 0)
+        .unwrap();
+    writer.end_directory().unwrap();
+    writer.end_directory().unwrap();
+    writer
+        .add_file(&CString::new("f3").unwrap(), 300, 50, 0o100644, 0, 0)
+        .unwrap();
+    writer.add_symlink(&CString::new("l").unwrap()).unwrap();
+    writer.finish().unwrap();
+
+    let mut reader = CatalogReader::new(Cursor::new(writer.writer));
+    assert_eq!(
+        reader.stats().unwrap(),
+        CatalogStats {
+            files: 3,
+            directories: 2,
+            other: 1,
+            total_size: 600,
+            oldest_mtime: Some(-5),
+            newest_mtime: Some(50),
+        }
+    );
+
+    // a root directory offset pointing past the end must fail with an error
+    let mut data = reader.reader.into_inner();
+    let len = data.len();
+    data[len - 8..].copy_from_slice(&(len as u64 + 100).to_le_bytes());
+    let mut reader = CatalogReader::new(Cursor::new(data));
+    assert!(reader.stats().is_err());
+}
+
+#[test]
+fn test_catalog_lookup_ci() {
+    use std::io::Cursor;
+
+    let mut writer = CatalogWriter::new(Vec::new()).unwrap();
+    writer
+        .start_directory(&CString::new("Documents").unwrap())
+        .unwrap();
+    writer
+        .add_file(&CString::new("Readme.TXT").unwrap(), 10, 0, 0o100644, 0, 0)
+        .unwrap();
+    writer
+        .add_file(&CString::new("readme.txt").unwrap(), 20, 0, 0o100644, 0, 0)
+        .unwrap();
+    writer
+        .add_file(&CString::new("Other.doc").unwrap(), 30, 0, 0o100644, 0, 0)
+        .unwrap();
+    writer.end_directory().unwrap();
+    writer.finish().unwrap();
+
+    let mut reader = CatalogReader::new(Cursor::new(writer.writer));
+    let root = reader.root().unwrap();
+
+    assert!(reader.lookup(&root, b"documents").unwrap().is_none());
+    let dir = reader.lookup_ci(&root, b"documents").unwrap().unwrap();
+    assert_eq!(dir.name, b"Documents");
+
+    assert!(reader.lookup_ci(&dir, b"missing.txt").unwrap().is_none());
+
+    let other = reader.lookup_ci(&dir, b"OTHER.DOC").unwrap().unwrap();
+    assert_eq!(other.name, b"Other.doc");
+
+    let all = reader.lookup_ci_all(&dir, b"README.txt").unwrap();
+    assert_eq!(all.len(), 2);
+    let first = reader.lookup_ci(&dir, b"README.txt").unwrap().unwrap();
+    assert_eq!(first.name, all[0].name);
+    assert!(all.iter().any(|entry| entry.name == b"Readme.TXT"));
+    assert!(all.iter().any(|entry| entry.name == b"readme.txt"));
+}
+
+#[test]
+fn test_catalog_finish_and_sync() {
+    let mut path = std::env::temp_dir();
+    path.push(format!("test-catalog-sync-{}.pcat1", std::process::id()));
+
+    let file = std::fs::File::create(&path).unwrap();
+    let mut writer = CatalogWriter::new(file).unwrap();
+    writer
+        .start_directory(&CString::new("dir").unwrap())
+        .unwrap();
+    writer
+        .add_file(&CString::new("file").unwrap(), 42, 0, 0o100644, 0, 0)
+        .unwrap();
+    writer.end_directory().unwrap();
+    writer.finish_and_sync().unwrap();
+    drop(writer);
+
+    let mut reader = CatalogReader::new(std::fs::File::open(&path).unwrap());
+    reader.root().unwrap();
+    let file = reader.lookup_recursive(b"/dir/file").unwrap();
+    assert_eq!(file.name, b"file");
+
+    let _ = std::fs::remove_file(&path);
 }
@@ -10,6 +10,7 @@ pub struct ChecksumReader<R> {
     reader: R,
     hasher: crc32fast::Hasher,
     signer: Option<Tied<Arc<CryptConfig>, openssl::sign::Signer<'static>>>,
+    bytes: u64,
 }
 
 impl<R: Read> ChecksumReader<R> {
@@ -29,18 +30,33 @@ impl<R: Read> ChecksumReader<R> {
             reader,
             hasher,
             signer,
+            bytes: 0,
         }
     }
 
-    pub fn finish(mut self) -> Result<(R, u32, Option<[u8; 32]>), Error> {
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// Note that reading from or seeking the underlying reader directly bypasses the checksum.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the number of bytes read through the checksum so far.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the inner reader, the CRC, the HMAC (if a CryptConfig was given) and the number
+    /// of bytes read.
+    pub fn finish(mut self) -> Result<(R, u32, Option<[u8; 32]>, u64), Error> {
         let crc = self.hasher.finalize();
 
         if let Some(ref mut signer) = self.signer {
             let mut tag = [0u8; 32];
             signer.sign(&mut tag)?;
-            Ok((self.reader, crc, Some(tag)))
+            Ok((self.reader, crc, Some(tag), self.bytes))
         } else {
-            Ok((self.reader, crc, None))
+            Ok((self.reader, crc, None, self.bytes))
         }
     }
 }
@@ -49,6 +65,7 @@ impl<R: Read> Read for ChecksumReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
         let count = self.reader.read(buf)?;
         if count > 0 {
+            self.bytes += count as u64;
             self.hasher.update(&buf[..count]);
             if let Some(ref mut signer) = self.signer {
                 signer.update(&buf[..count]).map_err(|err| {
@@ -1,3 +1,4 @@
+use std::io::Write;
 use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -76,7 +77,7 @@ impl ChunkStore {
         }
     }
 
-    fn chunk_dir<P: AsRef<Path>>(path: P) -> PathBuf {
+    pub(crate) fn chunk_dir<P: AsRef<Path>>(path: P) -> PathBuf {
         let mut chunk_dir: PathBuf = PathBuf::from(path.as_ref());
         chunk_dir.push(".chunks");
 
@@ -95,6 +96,23 @@ impl ChunkStore {
         worker: Option<&dyn WorkerTaskContext>,
         sync_level: DatastoreFSyncLevel,
     ) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        Self::create_with_chunk_path(name, path, None, uid, gid, worker, sync_level)
+    }
+
+    /// Like [`ChunkStore::create`], but places the chunk directory below `chunk_base` instead
+    /// of `path`, if set. Everything else (lock file, snapshots) stays below `path`.
+    pub fn create_with_chunk_path<P>(
+        name: &str,
+        path: P,
+        chunk_base: Option<PathBuf>,
+        uid: nix::unistd::Uid,
+        gid: nix::unistd::Gid,
+        worker: Option<&dyn WorkerTaskContext>,
+        sync_level: DatastoreFSyncLevel,
+    ) -> Result<Self, Error>
     where
         P: Into<PathBuf>,
     {
@@ -104,17 +122,21 @@ impl ChunkStore {
             bail!("expected absolute path - got {base:?}");
         }
 
-        let chunk_dir = Self::chunk_dir(&base);
+        let chunk_dir = Self::chunk_dir(chunk_base.as_ref().unwrap_or(&base));
 
         let options = CreateOptions::new().owner(uid).group(gid);
 
-        let default_options = CreateOptions::new();
-
-        match create_path(&base, Some(default_options), Some(options.clone())) {
-            Err(err) => bail!("unable to create chunk store '{name}' at {base:?} - {err}"),
-            Ok(res) => {
-                if !res {
-                    nix::unistd::chown(&base, Some(uid), Some(gid))?
+        for dir in std::iter::once(&base).chain(chunk_base.iter()) {
+            if !dir.is_absolute() {
+                bail!("expected absolute path - got {dir:?}");
+            }
+            let default_options = CreateOptions::new();
+            match create_path(dir, Some(default_options), Some(options.clone())) {
+                Err(err) => bail!("unable to create chunk store '{name}' at {dir:?} - {err}"),
+                Ok(res) => {
+                    if !res {
+                        nix::unistd::chown(dir, Some(uid), Some(gid))?
+                    }
                 }
             }
         }
@@ -150,7 +172,7 @@ impl ChunkStore {
             }
         }
 
-        Self::open(name, base, sync_level)
+        Self::open_with_chunk_path(name, base, chunk_base, sync_level)
     }
 
     fn lockfile_path<P: Into<PathBuf>>(base: P) -> PathBuf {
@@ -168,6 +190,18 @@ impl ChunkStore {
         name: &str,
         base: P,
         sync_level: DatastoreFSyncLevel,
+    ) -> Result<Self, Error> {
+        Self::open_with_chunk_path(name, base, None, sync_level)
+    }
+
+    /// Opens the chunk store, with the chunk directory below `chunk_base` if set.
+    ///
+    /// The same care as for [`ChunkStore::open`] applies.
+    pub(crate) fn open_with_chunk_path<P: Into<PathBuf>>(
+        name: &str,
+        base: P,
+        chunk_base: Option<PathBuf>,
+        sync_level: DatastoreFSyncLevel,
     ) -> Result<Self, Error> {
         let base: PathBuf = base.into();
 
@@ -175,7 +209,13 @@ impl ChunkStore {
             bail!("expected absolute path - got {:?}", base);
         }
 
-        let chunk_dir = Self::chunk_dir(&base);
+        let chunk_dir = match chunk_base {
+            Some(chunk_base) if !chunk_base.is_absolute() => {
+                bail!("expected absolute chunk path - got {:?}", chunk_base);
+            }
+            Some(chunk_base) => Self::chunk_dir(chunk_base),
+            None => Self::chunk_dir(&base),
+        };
 
         if let Err(err) = std::fs::metadata(&chunk_dir) {
             bail!("unable to open chunk store '{name}' at {chunk_dir:?} - {err}");
@@ -454,7 +494,10 @@ impl ChunkStore {
 
         let name = &self.name;
 
+        let mut overwrite = false;
+
         if let Ok(metadata) = std::fs::metadata(&chunk_path) {
+            overwrite = true;
             if !metadata.is_file() {
                 bail!("got unexpected file type on store '{name}' for chunk {digest_str}");
             }
@@ -500,18 +543,34 @@ impl ChunkStore {
             .parent()
             .ok_or_else(|| format_err!("unable to get chunk dir"))?;
 
-        proxmox_sys::fs::replace_file(
-            &chunk_path,
-            raw_data,
-            CreateOptions::new(),
-            self.sync_level == DatastoreFSyncLevel::File,
-        )
-        .map_err(|err| {
-            format_err!("inserting chunk on store '{name}' failed for {digest_str} - {err}")
-        })?;
+        if overwrite {
+            proxmox_sys::fs::replace_file(
+                &chunk_path,
+                raw_data,
+                CreateOptions::new(),
+                self.sync_level == DatastoreFSyncLevel::File,
+            )
+            .map_err(|err| {
+                format_err!("inserting chunk on store '{name}' failed for {digest_str} - {err}")
+            })?;
+        } else {
+            let created = self
+                .create_chunk_file(&chunk_path, raw_data)
+                .map_err(|err| {
+                    format_err!("inserting chunk on store '{name}' failed for {digest_str} - {err}")
+                })?;
+            if !created {
+                // our mutex only guards against inserts of this process, another one (e.g. an
+                // old proxy still running after a reload) inserted the same chunk meanwhile
+                log::debug!("chunk '{digest_str}' on store '{name}' was inserted concurrently");
+                let old_size = std::fs::metadata(&chunk_path)?.len();
+                self.touch_chunk(digest)?;
+                return Ok((true, old_size));
+            }
+        }
 
         if self.sync_level == DatastoreFSyncLevel::File {
-            // fsync dir handle to persist the tmp rename
+            // fsync dir handle to persist the new directory entry
             let dir = std::fs::File::open(chunk_dir_path)?;
             nix::unistd::fsync(dir.as_raw_fd())
                 .map_err(|err| format_err!("fsync failed: {err}"))?;
@@ -522,6 +581,39 @@ impl ChunkStore {
         Ok((false, encoded_size))
     }
 
+    /// Atomically create a new chunk file, returns `false` if the chunk already exists.
+    ///
+    /// The data is written to a temporary file first, which is then hard linked to the final
+    /// path. Unlike a rename, this never replaces an existing file. File systems without hard
+    /// link support fall back to a plain rename.
+    fn create_chunk_file(&self, chunk_path: &Path, raw_data: &[u8]) -> Result<bool, Error> {
+        let (mut tmp_file, tmp_path) =
+            proxmox_sys::fs::make_tmp_file(chunk_path, CreateOptions::new())?;
+
+        let result = proxmox_lang::try_block!({
+            tmp_file.write_all(raw_data)?;
+            if self.sync_level == DatastoreFSyncLevel::File {
+                tmp_file.sync_all()?;
+            }
+            match std::fs::hard_link(&tmp_path, chunk_path) {
+                Ok(()) => Ok(true),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+                Err(err)
+                    if err.kind() == std::io::ErrorKind::Unsupported
+                        || err.raw_os_error() == Some(nix::errno::Errno::EPERM as i32) =>
+                {
+                    std::fs::rename(&tmp_path, chunk_path)?;
+                    Ok(true)
+                }
+                Err(err) => Err(Error::from(err)),
+            }
+        });
+
+        let _ = std::fs::remove_file(&tmp_path);
+
+        result
+    }
+
     pub fn chunk_path(&self, digest: &[u8; 32]) -> (PathBuf, String) {
         // unwrap: only `None` in unit tests
         assert!(self.locker.is_some());
@@ -565,6 +657,16 @@ impl ChunkStore {
     }
 }
 
+#[test]
+fn test_verify_chunk_size() {
+    assert!(verify_chunk_size(64 * 1024).is_ok());
+    assert!(verify_chunk_size(4096 * 1024).is_ok());
+    assert!(verify_chunk_size(0).is_err());
+    assert!(verify_chunk_size(32 * 1024).is_err());
+    assert!(verify_chunk_size(100 * 1024).is_err());
+    assert!(verify_chunk_size(8192 * 1024).is_err());
+}
+
 #[test]
 fn test_chunk_store1() {
     let mut path = std::fs::canonicalize(".").unwrap(); // we need absolute path
@@ -610,3 +712,106 @@ fn test_chunk_store1() {
 
     if let Err(_e) = std::fs::remove_dir_all(".testdir") { /* ignore */ }
 }
+
+#[test]
+fn test_chunk_store_chunk_path() {
+    let mut path = std::fs::canonicalize(".").unwrap(); // we need absolute path
+    path.push(".testdir-chunk-path");
+    let base = path.join("meta");
+    let chunk_base = path.join("chunks");
+
+    if let Err(_e) = std::fs::remove_dir_all(&path) { /* ignore */ }
+
+    let user = nix::unistd::User::from_uid(nix::unistd::Uid::current())
+        .unwrap()
+        .unwrap();
+    let chunk_store = ChunkStore::create_with_chunk_path(
+        "test",
+        &base,
+        Some(chunk_base.clone()),
+        user.uid,
+        user.gid,
+        None,
+        DatastoreFSyncLevel::None,
+    )
+    .unwrap();
+
+    let (chunk, digest) = crate::data_blob::DataChunkBuilder::new(&[0u8, 1u8])
+        .build()
+        .unwrap();
+    chunk_store.insert_chunk(&chunk, &digest).unwrap();
+
+    let (chunk_path, _) = chunk_store.chunk_path(&digest);
+    assert!(chunk_path.starts_with(chunk_base.join(".chunks")));
+    assert!(chunk_path.exists());
+    assert_eq!(chunk_store.base_path(), base);
+    assert!(!base.join(".chunks").exists());
+
+    // the chunk directory is only found with the override
+    assert!(ChunkStore::open("test", &base, DatastoreFSyncLevel::None).is_err());
+
+    if let Err(_e) = std::fs::remove_dir_all(&path) { /* ignore */ }
+}
+
+#[test]
+fn test_chunk_store_concurrent_insert() {
+    let mut path = std::fs::canonicalize(".").unwrap(); // we need absolute path
+    path.push(".testdir-concurrent-insert");
+
+    if let Err(_e) = std::fs::remove_dir_all(&path) { /* ignore */ }
+
+    let user = nix::unistd::User::from_uid(nix::unistd::Uid::current())
+        .unwrap()
+        .unwrap();
+    let chunk_store = ChunkStore::create(
+        "test",
+        &path,
+        user.uid,
+        user.gid,
+        None,
+        DatastoreFSyncLevel::None,
+    )
+    .unwrap();
+    // a second instance has its own mutex, like a store opened by another process
+    let stores = [
+        Arc::new(chunk_store),
+        Arc::new(ChunkStore::open("test", &path, DatastoreFSyncLevel::None).unwrap()),
+    ];
+
+    let data: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+    let (chunk, digest) = crate::data_blob::DataChunkBuilder::new(&data)
+        .build()
+        .unwrap();
+    let chunk = Arc::new(chunk);
+
+    let barrier = Arc::new(std::sync::Barrier::new(8));
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let store = Arc::clone(&stores[i % 2]);
+            let chunk = Arc::clone(&chunk);
+            let barrier = Arc::clone(&barrier);
+            std::thread::spawn(move || {
+                barrier.wait();
+                store.insert_chunk(&chunk, &digest).unwrap()
+            })
+        })
+        .collect();
+
+    let results: Vec<(bool, u64)> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    let stored_bytes: u64 = results
+        .iter()
+        .filter(|(is_duplicate, _)| !is_duplicate)
+        .map(|(_, size)| size)
+        .sum();
+    assert_eq!(stored_bytes, chunk.raw_size());
+    assert!(results.iter().all(|(_, size)| *size == chunk.raw_size()));
+
+    let (chunk_path, _) = stores[0].chunk_path(&digest);
+    assert_eq!(std::fs::read(&chunk_path).unwrap(), chunk.raw_data());
+    // no temporary files left behind
+    let entries = std::fs::read_dir(chunk_path.parent().unwrap()).unwrap();
+    assert_eq!(entries.count(), 1);
+
+    if let Err(_e) = std::fs::remove_dir_all(&path) { /* ignore */ }
+}
@@ -36,6 +36,11 @@ impl<R: BufRead> CryptReader<R> {
         })
     }
 
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
     pub fn finish(self) -> Result<R, Error> {
         if !self.finalized {
             bail!("CryptReader not successfully finalized.");
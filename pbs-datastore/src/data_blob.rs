@@ -10,7 +10,10 @@ use pbs_tools::crypt_config::CryptConfig;
 
 use super::file_formats::*;
 
-const MAX_BLOB_SIZE: usize = 128 * 1024 * 1024;
+pub(crate) const MAX_BLOB_SIZE: usize = 128 * 1024 * 1024;
+
+/// Default zstd compression level used by [`DataBlob::encode`]
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 1;
 
 /// Encoded data chunk with digest and positional information
 pub struct ChunkInfo {
@@ -88,6 +91,16 @@ impl DataBlob {
         data: &[u8],
         config: Option<&CryptConfig>,
         compress: bool,
+    ) -> Result<Self, Error> {
+        Self::encode_with_level(data, config, compress, DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    /// Create a DataBlob like [`DataBlob::encode`], using the given zstd compression level
+    pub fn encode_with_level(
+        data: &[u8],
+        config: Option<&CryptConfig>,
+        compress: bool,
+        compression_level: i32,
     ) -> Result<Self, Error> {
         if data.len() > MAX_BLOB_SIZE {
             bail!("data blob too large ({} bytes).", data.len());
@@ -96,7 +109,7 @@ impl DataBlob {
         let mut blob = if let Some(config) = config {
             let compr_data;
             let (_compress, data, magic) = if compress {
-                compr_data = zstd::bulk::compress(data, 1)?;
+                compr_data = zstd::bulk::compress(data, compression_level)?;
                 // Note: We only use compression if result is shorter
                 if compr_data.len() < data.len() {
                     (true, &compr_data[..], ENCR_COMPR_BLOB_MAGIC_1_0)
@@ -148,7 +161,7 @@ impl DataBlob {
                     comp_data.write_le_value(head)?;
                 }
 
-                zstd::stream::copy_encode(data, &mut comp_data, 1)?;
+                zstd::stream::copy_encode(data, &mut comp_data, compression_level)?;
 
                 if comp_data.len() < max_data_len {
                     let mut blob = DataBlob {
@@ -1,4 +1,4 @@
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::sync::Arc;
 
 use anyhow::{bail, format_err, Error};
@@ -9,7 +9,10 @@ use pbs_tools::crypt_config::CryptConfig;
 
 use crate::checksum_reader::ChecksumReader;
 use crate::crypt_reader::CryptReader;
-use crate::file_formats::{self, DataBlobHeader};
+use crate::file_formats::{self, DataBlobHeader, EncryptedDataBlobHeader};
+
+/// Default capacity of the read buffer used for encrypted blobs
+pub const DEFAULT_BLOB_READER_BUFFER_SIZE: usize = 64 * 1024;
 
 enum BlobReaderState<'reader, R: Read> {
     Uncompressed {
@@ -34,39 +37,109 @@ enum BlobReaderState<'reader, R: Read> {
 }
 
 /// Read data blobs
+///
+/// If the underlying reader implements [`Seek`], so does the blob reader:
+///
+/// - uncompressed, unencrypted blobs store their payload as is, so seeking is O(1) in both
+///   directions. Since the skipped data is never read, [`finish`](Self::finish) cannot verify
+///   the CRC after such a seek and skips that check.
+/// - compressed and/or encrypted blobs only support forward seeks, which decode and discard the
+///   data in between. Seeking backwards or relative to the end is not supported.
 pub struct DataBlobReader<'reader, R: Read> {
     state: BlobReaderState<'reader, R>,
+    // position in the decoded data stream
+    position: u64,
+    // set if data was skipped without reading it, invalidating the CRC
+    skipped_data: bool,
+    // size of the blob header, including IV and tag for encrypted blobs
+    header_size: u64,
 }
 
 // zstd_safe::DCtx is not sync but we are, since
 // the only public interface is on mutable reference
 unsafe impl<R: Read> Sync for DataBlobReader<'_, R> {}
 
-impl<R: Read> DataBlobReader<'_, R> {
-    pub fn new(mut reader: R, config: Option<Arc<CryptConfig>>) -> Result<Self, Error> {
+impl<'reader, R: Read> DataBlobReader<'reader, R> {
+    fn with_state(state: BlobReaderState<'reader, R>) -> Self {
+        let header_size = match state {
+            BlobReaderState::Uncompressed { .. } | BlobReaderState::Compressed { .. } => {
+                std::mem::size_of::<DataBlobHeader>()
+            }
+            BlobReaderState::Encrypted { .. } | BlobReaderState::EncryptedCompressed { .. } => {
+                std::mem::size_of::<EncryptedDataBlobHeader>()
+            }
+        };
+        Self {
+            state,
+            position: 0,
+            skipped_data: false,
+            header_size: header_size as u64,
+        }
+    }
+
+    /// Returns the number of raw blob bytes (including the header) consumed from the underlying
+    /// reader so far. Once all data is read, this is the size of the blob.
+    ///
+    /// Data skipped by seeking in uncompressed blobs is not counted.
+    pub fn raw_size(&self) -> u64 {
+        let payload = match &self.state {
+            BlobReaderState::Uncompressed { csum_reader, .. } => csum_reader.bytes(),
+            BlobReaderState::Compressed { decompr, .. } => decompr.get_ref().get_ref().bytes(),
+            BlobReaderState::Encrypted { decrypt_reader, .. } => {
+                decrypt_reader.get_ref().get_ref().bytes()
+            }
+            BlobReaderState::EncryptedCompressed { decompr, .. } => {
+                decompr.get_ref().get_ref().get_ref().get_ref().bytes()
+            }
+        };
+        self.header_size + payload
+    }
+
+    /// Returns the number of decoded bytes read so far. Once all data is read, this is the size
+    /// of the decoded blob.
+    pub fn decoded_size(&self) -> u64 {
+        self.position
+    }
+
+    /// Create a new reader for the blob data provided by `reader`.
+    ///
+    /// `buffer_size` sets the capacity of the read buffers used for compressed and encrypted
+    /// blobs. Bigger buffers can speed up large sequential reads. If not set, encrypted data is
+    /// read with a buffer of [`DEFAULT_BLOB_READER_BUFFER_SIZE`] and the decompressor uses the
+    /// input size recommended by zstd.
+    pub fn new(
+        mut reader: R,
+        config: Option<Arc<CryptConfig>>,
+        buffer_size: Option<usize>,
+    ) -> Result<Self, Error> {
+        if buffer_size == Some(0) {
+            bail!("blob reader buffer size must not be zero");
+        }
+        let crypt_buffer_size = buffer_size.unwrap_or(DEFAULT_BLOB_READER_BUFFER_SIZE);
         let head: DataBlobHeader = unsafe { reader.read_le_value()? };
         match head.magic {
             file_formats::UNCOMPRESSED_BLOB_MAGIC_1_0 => {
                 let expected_crc = u32::from_le_bytes(head.crc);
                 let csum_reader = ChecksumReader::new(reader, None);
-                Ok(Self {
-                    state: BlobReaderState::Uncompressed {
-                        expected_crc,
-                        csum_reader,
-                    },
-                })
+                Ok(Self::with_state(BlobReaderState::Uncompressed {
+                    expected_crc,
+                    csum_reader,
+                }))
             }
             file_formats::COMPRESSED_BLOB_MAGIC_1_0 => {
                 let expected_crc = u32::from_le_bytes(head.crc);
                 let csum_reader = ChecksumReader::new(reader, None);
 
-                let decompr = zstd::stream::read::Decoder::new(csum_reader)?;
-                Ok(Self {
-                    state: BlobReaderState::Compressed {
-                        expected_crc,
-                        decompr,
-                    },
-                })
+                let decompr = match buffer_size {
+                    Some(size) => zstd::stream::read::Decoder::with_buffer(
+                        BufReader::with_capacity(size, csum_reader),
+                    )?,
+                    None => zstd::stream::read::Decoder::new(csum_reader)?,
+                };
+                Ok(Self::with_state(BlobReaderState::Compressed {
+                    expected_crc,
+                    decompr,
+                }))
             }
             file_formats::ENCRYPTED_BLOB_MAGIC_1_0 => {
                 let config = config
@@ -78,17 +151,15 @@ impl<R: Read> DataBlobReader<'_, R> {
                 reader.read_exact(&mut expected_tag)?;
                 let csum_reader = ChecksumReader::new(reader, None);
                 let decrypt_reader = CryptReader::new(
-                    BufReader::with_capacity(64 * 1024, csum_reader),
+                    BufReader::with_capacity(crypt_buffer_size, csum_reader),
                     iv,
                     expected_tag,
                     config,
                 )?;
-                Ok(Self {
-                    state: BlobReaderState::Encrypted {
-                        expected_crc,
-                        decrypt_reader,
-                    },
-                })
+                Ok(Self::with_state(BlobReaderState::Encrypted {
+                    expected_crc,
+                    decrypt_reader,
+                }))
             }
             file_formats::ENCR_COMPR_BLOB_MAGIC_1_0 => {
                 let config = config
@@ -100,18 +171,21 @@ impl<R: Read> DataBlobReader<'_, R> {
                 reader.read_exact(&mut expected_tag)?;
                 let csum_reader = ChecksumReader::new(reader, None);
                 let decrypt_reader = CryptReader::new(
-                    BufReader::with_capacity(64 * 1024, csum_reader),
+                    BufReader::with_capacity(crypt_buffer_size, csum_reader),
                     iv,
                     expected_tag,
                     config,
                 )?;
-                let decompr = zstd::stream::read::Decoder::new(decrypt_reader)?;
-                Ok(Self {
-                    state: BlobReaderState::EncryptedCompressed {
-                        expected_crc,
-                        decompr,
-                    },
-                })
+                let decompr = match buffer_size {
+                    Some(size) => zstd::stream::read::Decoder::with_buffer(
+                        BufReader::with_capacity(size, decrypt_reader),
+                    )?,
+                    None => zstd::stream::read::Decoder::new(decrypt_reader)?,
+                };
+                Ok(Self::with_state(BlobReaderState::EncryptedCompressed {
+                    expected_crc,
+                    decompr,
+                }))
             }
             _ => bail!("got wrong magic number {:?}", head.magic),
         }
@@ -123,8 +197,8 @@ impl<R: Read> DataBlobReader<'_, R> {
                 csum_reader,
                 expected_crc,
             } => {
-                let (reader, crc, _) = csum_reader.finish()?;
-                if crc != expected_crc {
+                let (reader, crc, _, _) = csum_reader.finish()?;
+                if !self.skipped_data && crc != expected_crc {
                     bail!("blob crc check failed");
                 }
                 Ok(reader)
@@ -134,7 +208,7 @@ impl<R: Read> DataBlobReader<'_, R> {
                 decompr,
             } => {
                 let csum_reader = decompr.finish().into_inner();
-                let (reader, crc, _) = csum_reader.finish()?;
+                let (reader, crc, _, _) = csum_reader.finish()?;
                 if crc != expected_crc {
                     bail!("blob crc check failed");
                 }
@@ -145,7 +219,7 @@ impl<R: Read> DataBlobReader<'_, R> {
                 decrypt_reader,
             } => {
                 let csum_reader = decrypt_reader.finish()?.into_inner();
-                let (reader, crc, _) = csum_reader.finish()?;
+                let (reader, crc, _, _) = csum_reader.finish()?;
                 if crc != expected_crc {
                     bail!("blob crc check failed");
                 }
@@ -157,7 +231,7 @@ impl<R: Read> DataBlobReader<'_, R> {
             } => {
                 let decrypt_reader = decompr.finish().into_inner();
                 let csum_reader = decrypt_reader.finish()?.into_inner();
-                let (reader, crc, _) = csum_reader.finish()?;
+                let (reader, crc, _, _) = csum_reader.finish()?;
                 if crc != expected_crc {
                     bail!("blob crc check failed");
                 }
@@ -169,11 +243,71 @@ impl<R: Read> DataBlobReader<'_, R> {
 
 impl<R: Read> Read for DataBlobReader<'_, R> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
-        match &mut self.state {
+        let count = match &mut self.state {
             BlobReaderState::Uncompressed { csum_reader, .. } => csum_reader.read(buf),
             BlobReaderState::Compressed { decompr, .. } => decompr.read(buf),
             BlobReaderState::Encrypted { decrypt_reader, .. } => decrypt_reader.read(buf),
             BlobReaderState::EncryptedCompressed { decompr, .. } => decompr.read(buf),
+        }?;
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl<R: Read + Seek> Seek for DataBlobReader<'_, R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, std::io::Error> {
+        if let BlobReaderState::Uncompressed { csum_reader, .. } = &mut self.state {
+            let reader = csum_reader.get_mut();
+            let current = reader.stream_position()?;
+            let data_start = current - self.position;
+
+            let target = match pos {
+                SeekFrom::Start(offset) => data_start.checked_add(offset),
+                SeekFrom::Current(offset) => current.checked_add_signed(offset),
+                SeekFrom::End(offset) => reader.seek(SeekFrom::End(0))?.checked_add_signed(offset),
+            };
+            let target = match target {
+                Some(target) if target >= data_start => target,
+                _ => {
+                    // restore the position, we might have seeked to the end above
+                    reader.seek(SeekFrom::Start(current))?;
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "invalid seek to a negative or overflowing position",
+                    ));
+                }
+            };
+
+            reader.seek(SeekFrom::Start(target))?;
+            if target != current {
+                self.skipped_data = true;
+            }
+            self.position = target - data_start;
+            return Ok(self.position);
+        }
+
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "cannot seek relative to the end of a compressed or encrypted blob",
+                ))
+            }
+        };
+
+        match target {
+            Some(target) if target >= self.position => {
+                let count = target - self.position;
+                // read() keeps track of the position
+                std::io::copy(&mut self.by_ref().take(count), &mut std::io::sink())?;
+                Ok(self.position)
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "cannot seek backwards in a compressed or encrypted blob",
+            )),
         }
     }
 }
@@ -38,6 +38,9 @@ lazy_static! {
         Mutex::new(HashMap::new());
 }
 
+/// Default for the `max-backup-time-skew` tuning option, in seconds
+pub const DEFAULT_MAX_BACKUP_TIME_SKEW: i64 = 300;
+
 /// checks if auth_id is owner, or, if owner is a token, if
 /// auth_id is the user of the token
 pub fn check_backup_owner(owner: &Authid, auth_id: &Authid) -> Result<(), Error> {
@@ -61,6 +64,7 @@ pub struct DataStoreImpl {
     chunk_order: ChunkOrder,
     last_digest: Option<[u8; 32]>,
     sync_level: DatastoreFSyncLevel,
+    max_backup_time_skew: i64,
 }
 
 impl DataStoreImpl {
@@ -75,6 +79,7 @@ impl DataStoreImpl {
             chunk_order: Default::default(),
             last_digest: None,
             sync_level: Default::default(),
+            max_backup_time_skew: DEFAULT_MAX_BACKUP_TIME_SKEW,
         })
     }
 }
@@ -183,9 +188,10 @@ impl DataStore {
                 DatastoreTuning::API_SCHEMA
                     .parse_property_string(config.tuning.as_deref().unwrap_or(""))?,
             )?;
-            Arc::new(ChunkStore::open(
+            Arc::new(ChunkStore::open_with_chunk_path(
                 name,
                 &config.path,
+                config.chunk_path.as_ref().map(PathBuf::from),
                 tuning.sync_level.unwrap_or_default(),
             )?)
         };
@@ -265,8 +271,12 @@ impl DataStore {
             DatastoreTuning::API_SCHEMA
                 .parse_property_string(config.tuning.as_deref().unwrap_or(""))?,
         )?;
-        let chunk_store =
-            ChunkStore::open(&name, &config.path, tuning.sync_level.unwrap_or_default())?;
+        let chunk_store = ChunkStore::open_with_chunk_path(
+            &name,
+            &config.path,
+            config.chunk_path.as_ref().map(PathBuf::from),
+            tuning.sync_level.unwrap_or_default(),
+        )?;
         let inner = Arc::new(Self::with_store_and_config(
             Arc::new(chunk_store),
             config,
@@ -313,6 +323,9 @@ impl DataStore {
             chunk_order: tuning.chunk_order.unwrap_or_default(),
             last_digest,
             sync_level: tuning.sync_level.unwrap_or_default(),
+            max_backup_time_skew: tuning
+                .max_backup_time_skew
+                .unwrap_or(DEFAULT_MAX_BACKUP_TIME_SKEW),
         })
     }
 
@@ -666,15 +679,21 @@ impl DataStore {
     ) -> Result<(), Error> {
         let path = self.owner_path(ns, backup_group);
 
+        if force {
+            // replace atomically, so that readers never see a truncated owner file
+            return replace_file(
+                &path,
+                format!("{auth_id}\n").as_bytes(),
+                CreateOptions::new(),
+                false,
+            )
+            .map_err(|err| format_err!("unable to replace owner file {:?} - {}", path, err));
+        }
+
         let mut open_options = std::fs::OpenOptions::new();
         open_options.write(true);
         open_options.truncate(true);
-
-        if force {
-            open_options.create(true);
-        } else {
-            open_options.create_new(true);
-        }
+        open_options.create_new(true);
 
         let mut file = open_options
             .open(&path)
@@ -1244,6 +1263,11 @@ impl DataStore {
         self.inner.verify_new
     }
 
+    /// Maximum number of seconds a new backup may be dated ahead of the current time
+    pub fn max_backup_time_skew(&self) -> i64 {
+        self.inner.max_backup_time_skew
+    }
+
     /// returns a list of chunks sorted by their inode number on disk chunks that couldn't get
     /// stat'ed are placed at the end of the list
     pub fn get_chunks_in_order<F, A>(
@@ -1406,6 +1430,7 @@ impl DataStore {
         }
 
         let base = PathBuf::from(&datastore_config.path);
+        let chunk_base = PathBuf::from(datastore_config.chunk_base_path());
 
         let mut ok = true;
         if destroy_data {
@@ -1435,7 +1460,12 @@ impl DataStore {
 
             // chunks get removed last and only if the backups were successfully deleted
             if ok {
-                remove(".chunks", &mut ok);
+                if let Err(err) = std::fs::remove_dir_all(ChunkStore::chunk_dir(&chunk_base)) {
+                    if err.kind() != io::ErrorKind::NotFound {
+                        task_warn!(worker, "failed to remove \".chunks\" subdirectory: {err}");
+                        ok = false;
+                    }
+                }
             }
         }
 
@@ -90,8 +90,18 @@ impl FixedIndexReader {
         let ctime = i64::from_le(header.ctime);
         let chunk_size = u64::from_le(header.chunk_size);
 
-        let index_length = ((size + chunk_size - 1) / chunk_size) as usize;
-        let index_size = index_length * 32;
+        if chunk_size == 0 {
+            bail!("got invalid chunk size 0");
+        }
+
+        let index_length = match size.checked_add(chunk_size - 1) {
+            Some(size) => (size / chunk_size) as usize,
+            None => bail!("got invalid index size {size}"),
+        };
+        let index_size = match index_length.checked_mul(32) {
+            Some(index_size) => index_size,
+            None => bail!("got invalid index size {size}"),
+        };
 
         let expected_index_size = (stat.st_size as usize) - header_size;
         if index_size != expected_index_size {
@@ -459,3 +469,37 @@ impl FixedIndexWriter {
         Ok(())
     }
 }
+
+#[test]
+fn test_fixed_index_invalid_header() {
+    let mut path = std::fs::canonicalize(".").unwrap(); // we need absolute path
+    path.push(".testdir-fixed-index-header");
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+
+    let write_index = |size: u64, chunk_size: u64, digests: usize| {
+        let mut header = vec![0u8; std::mem::size_of::<FixedIndexHeader>()];
+        header[..8].copy_from_slice(&file_formats::FIXED_SIZED_CHUNK_INDEX_1_0);
+        header[64..72].copy_from_slice(&size.to_le_bytes());
+        header[72..80].copy_from_slice(&chunk_size.to_le_bytes());
+        header.resize(header.len() + digests * 32, 0);
+
+        let file = path.join("test.fidx");
+        std::fs::write(&file, header).unwrap();
+        FixedIndexReader::open(&file)
+    };
+
+    let index = write_index(8192, 4096, 2).unwrap();
+    assert_eq!(index.index_count(), 2);
+    drop(index);
+
+    // corrupted headers must not panic
+    assert!(write_index(8192, 0, 2).is_err());
+    assert!(write_index(u64::MAX, 4096, 2).is_err());
+    // index length times digest size overflows
+    assert!(write_index((1 << 59) + 1, 1, 2).is_err());
+    // digest area does not match the declared size
+    assert!(write_index(8192, 4096, 3).is_err());
+
+    let _ = std::fs::remove_dir_all(&path);
+}
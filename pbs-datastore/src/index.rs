@@ -1,6 +1,23 @@
 use std::collections::HashMap;
 use std::ops::Range;
 
+use anyhow::{bail, format_err, Error};
+use hex::FromHex;
+
+/// Parse a hex encoded SHA-256 digest, as used for chunks and index checksums.
+///
+/// Unlike a plain `from_hex`, the error tells what was expected.
+pub fn parse_digest(digest: &str) -> Result<[u8; 32], Error> {
+    if digest.len() != 64 {
+        bail!(
+            "invalid digest '{}' - expected 64 hex digits (32 bytes), got {} characters",
+            digest.chars().take(80).collect::<String>(),
+            digest.len(),
+        );
+    }
+    <[u8; 32]>::from_hex(digest).map_err(|err| format_err!("invalid digest '{digest}' - {err}"))
+}
+
 #[derive(Clone)]
 pub struct ChunkReadInfo {
     pub range: Range<u64>,
@@ -67,3 +84,14 @@ pub trait IndexFile {
         map
     }
 }
+
+#[test]
+fn test_parse_digest() {
+    let digest = [0xabu8; 32];
+    assert_eq!(parse_digest(&hex::encode(digest)).unwrap(), digest);
+
+    let err = parse_digest(&hex::encode([0xabu8; 20])).unwrap_err();
+    assert!(err.to_string().contains("expected 64 hex digits"));
+    assert!(parse_digest("").is_err());
+    assert!(parse_digest(&"z".repeat(64)).is_err());
+}
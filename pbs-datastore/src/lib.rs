@@ -167,6 +167,7 @@ macro_rules! PROXMOX_BACKUP_READER_PROTOCOL_ID_V1 {
     };
 }
 
+pub mod async_data_blob_reader;
 pub mod backup_info;
 pub mod cached_chunk_reader;
 pub mod catalog;
@@ -192,6 +193,7 @@ pub mod task_tracking;
 pub mod dynamic_index;
 pub mod fixed_index;
 
+pub use async_data_blob_reader::AsyncDataBlobReader;
 pub use backup_info::{BackupDir, BackupGroup, BackupInfo};
 pub use checksum_reader::ChecksumReader;
 pub use checksum_writer::ChecksumWriter;
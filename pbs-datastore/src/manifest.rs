@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use anyhow::{bail, format_err, Error};
+use hex::FromHex;
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -185,6 +186,28 @@ impl BackupManifest {
         }
     }
 
+    /// Checks that the `signature` and `key-fingerprint` fields of a signed manifest parse.
+    ///
+    /// This does not verify the signature, which needs the key and so is only possible on the
+    /// client side (see [`BackupManifest::from_data`]). It only catches truncated or otherwise
+    /// mangled fields.
+    pub fn check_signed_fields(&self) -> Result<(), Error> {
+        let signature = match &self.signature {
+            Some(signature) => signature,
+            None => return Ok(()),
+        };
+
+        match <[u8; 32]>::from_hex(signature) {
+            Ok(_) => {}
+            Err(err) => bail!("malformed manifest signature '{}' - {}", signature, err),
+        }
+
+        self.fingerprint()
+            .map_err(|err| format_err!("malformed key fingerprint in signed manifest - {}", err))?;
+
+        Ok(())
+    }
+
     /// Checks if a BackupManifest and a CryptConfig share a valid fingerprint combination.
     ///
     /// An unsigned manifest is valid with any or no CryptConfig.
@@ -305,3 +328,32 @@ fn test_manifest_signature() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_manifest_signed_fields() -> Result<(), Error> {
+    let mut manifest = BackupManifest::new("host/elsa/2020-06-26T13:56:05Z".parse()?);
+    manifest.add_file("test1.img.fidx".into(), 200, [1u8; 32], CryptMode::Encrypt)?;
+
+    // unsigned manifests are always fine
+    manifest.check_signed_fields()?;
+
+    manifest.signature =
+        Some("d7b446fb7db081662081d4b40fedd858a1d6307a5aff4ecff7d5bf4fd35679e9".to_string());
+    manifest.check_signed_fields()?;
+
+    // truncated and non-hex signatures
+    manifest.signature =
+        Some("d7b446fb7db081662081d4b40fedd858a1d6307a5aff4ecff7d5bf4fd35679".to_string());
+    assert!(manifest.check_signed_fields().is_err());
+    manifest.signature =
+        Some("x7b446fb7db081662081d4b40fedd858a1d6307a5aff4ecff7d5bf4fd35679e9".to_string());
+    assert!(manifest.check_signed_fields().is_err());
+
+    // corrupt key fingerprint
+    manifest.signature =
+        Some("d7b446fb7db081662081d4b40fedd858a1d6307a5aff4ecff7d5bf4fd35679e9".to_string());
+    manifest.unprotected["key-fingerprint"] = "not-a-fingerprint".into();
+    assert!(manifest.check_signed_fields().is_err());
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use std::path::PathBuf;
 
 use anyhow::Error;
 
-use pbs_api_types::KeepOptions;
+use pbs_api_types::{KeepOptions, SnapshotVerifyState, VerifyState};
 
 use super::BackupInfo;
 
@@ -103,10 +103,63 @@ fn remove_incomplete_snapshots(mark: &mut HashMap<PathBuf, PruneMark>, list: &[B
     }
 }
 
+/// Keep the `keep` most recent snapshots which were verified successfully.
+///
+/// Unlike the other keep options this does not only consider snapshots not covered yet, it
+/// guarantees that the selected snapshots are kept, whatever the other options decided.
+fn mark_last_verified<F: Fn(&BackupInfo) -> bool>(
+    mark: &mut HashMap<PathBuf, PruneMark>,
+    list: &[BackupInfo],
+    keep: usize,
+    is_verified: F,
+) {
+    let mut kept = 0;
+    for info in list {
+        if kept >= keep {
+            break;
+        }
+        if !info.is_finished() || !is_verified(info) {
+            continue;
+        }
+        kept += 1;
+
+        let backup_id = info.backup_dir.relative_path();
+        if !mark.get(&backup_id).map(|m| m.keep()).unwrap_or(false) {
+            mark.insert(backup_id, PruneMark::Keep);
+        }
+    }
+}
+
+/// Check the manifest of a snapshot for a successful verification.
+fn snapshot_verified_ok(info: &BackupInfo) -> bool {
+    let manifest = match info.backup_dir.load_manifest() {
+        Ok((manifest, _)) => manifest,
+        Err(_) => return false,
+    };
+    let raw_verify_state = manifest.unprotected["verify_state"].clone();
+    matches!(
+        serde_json::from_value::<SnapshotVerifyState>(raw_verify_state),
+        Ok(SnapshotVerifyState {
+            state: VerifyState::Ok,
+            ..
+        })
+    )
+}
+
 /// This filters incomplete and kept backups.
 pub fn compute_prune_info(
+    list: Vec<BackupInfo>,
+    options: &KeepOptions,
+) -> Result<Vec<(BackupInfo, PruneMark)>, Error> {
+    compute_prune_info_with_verify_state(list, options, snapshot_verified_ok)
+}
+
+/// Like [`compute_prune_info`], but uses `is_verified` instead of the snapshot manifests to
+/// decide which snapshots count for `keep-last-verified`.
+pub fn compute_prune_info_with_verify_state<F: Fn(&BackupInfo) -> bool>(
     mut list: Vec<BackupInfo>,
     options: &KeepOptions,
+    is_verified: F,
 ) -> Result<Vec<(BackupInfo, PruneMark)>, Error> {
     let mut mark = HashMap::new();
 
@@ -154,6 +207,10 @@ pub fn compute_prune_info(
         })?;
     }
 
+    if let Some(keep_last_verified) = options.keep_last_verified {
+        mark_last_verified(&mut mark, &list, keep_last_verified as usize, is_verified);
+    }
+
     let prune_info: Vec<(BackupInfo, PruneMark)> = list
         .into_iter()
         .map(|info| {
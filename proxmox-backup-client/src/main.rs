@@ -1034,6 +1034,7 @@ async fn create_backup(
                 let upload_options = UploadOptions {
                     previous_manifest: previous_manifest.clone(),
                     fixed_size: Some(size),
+                    fixed_chunk_size: chunk_size_opt.map(|size| size as u64),
                     compress: true,
                     encrypt: crypto.mode == CryptMode::Encrypt,
                 };
@@ -1,4 +1,5 @@
 use anyhow::Error;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use proxmox_router::cli::*;
@@ -8,10 +9,47 @@ use pbs_api_types::percent_encoding::percent_encode_component;
 use pbs_client::display_task_log;
 use pbs_tools::json::required_string_param;
 
-use pbs_api_types::UPID;
+use pbs_api_types::{TaskStateType, UPID};
 
 use crate::{complete_repository, connect, extract_repository_from_value, REPO_URL_SCHEMA};
 
+#[api]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Filter the task list by task status.
+enum TaskStatusFilter {
+    /// List running and stopped tasks.
+    All,
+    /// Only list tasks which finished successfully.
+    Ok,
+    /// Only list failed tasks.
+    Error,
+    /// Only list tasks which finished with warnings.
+    Warning,
+    /// Only list running tasks.
+    Running,
+}
+
+/// Returns the task list query parameters for the given status filter.
+///
+/// Without a status filter, only running tasks are listed unless `all` is set.
+fn status_filter_params(status_filter: Option<TaskStatusFilter>, all: bool) -> Value {
+    let (running, state) = match status_filter {
+        None => (!all, None),
+        Some(TaskStatusFilter::All) => (false, None),
+        Some(TaskStatusFilter::Running) => (true, None),
+        Some(TaskStatusFilter::Ok) => (false, Some(TaskStateType::OK)),
+        Some(TaskStatusFilter::Error) => (false, Some(TaskStateType::Error)),
+        Some(TaskStatusFilter::Warning) => (false, Some(TaskStateType::Warning)),
+    };
+
+    let mut params = json!({ "running": running });
+    if let Some(state) = state {
+        params["statusfilter"] = json!([state]);
+    }
+    params
+}
+
 #[api(
     input: {
         properties: {
@@ -36,6 +74,10 @@ use crate::{complete_repository, connect, extract_repository_from_value, REPO_UR
                 description: "Also list stopped tasks.",
                 optional: true,
             },
+            "status-filter": {
+                type: TaskStatusFilter,
+                optional: true,
+            },
         }
     }
 )]
@@ -47,15 +89,17 @@ async fn task_list(param: Value) -> Result<Value, Error> {
     let client = connect(&repo)?;
 
     let limit = param["limit"].as_u64().unwrap_or(50) as usize;
-    let running = !param["all"].as_bool().unwrap_or(false);
-
-    let args = json!({
-        "running": running,
-        "start": 0,
-        "limit": limit,
-        "userfilter": repo.auth_id(),
-        "store": repo.store(),
-    });
+    let all = param["all"].as_bool().unwrap_or(false);
+    let status_filter: Option<TaskStatusFilter> = match param.get("status-filter") {
+        Some(filter) => Some(serde_json::from_value(filter.clone())?),
+        None => None,
+    };
+
+    let mut args = status_filter_params(status_filter, all);
+    args["start"] = 0.into();
+    args["limit"] = limit.into();
+    args["userfilter"] = repo.auth_id().to_string().into();
+    args["store"] = repo.store().into();
 
     let mut result = client
         .get("api2/json/nodes/localhost/tasks", Some(args))
@@ -151,3 +195,40 @@ pub fn task_mgmt_cli() -> CliCommandMap {
         .insert("list", task_list_cmd_def)
         .insert("stop", task_stop_cmd_def)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_status_filter_params() {
+        assert_eq!(
+            status_filter_params(None, false),
+            json!({ "running": true })
+        );
+        assert_eq!(
+            status_filter_params(None, true),
+            json!({ "running": false })
+        );
+        assert_eq!(
+            status_filter_params(Some(TaskStatusFilter::All), false),
+            json!({ "running": false }),
+        );
+        assert_eq!(
+            status_filter_params(Some(TaskStatusFilter::Running), true),
+            json!({ "running": true }),
+        );
+        assert_eq!(
+            status_filter_params(Some(TaskStatusFilter::Ok), false),
+            json!({ "running": false, "statusfilter": ["ok"] }),
+        );
+        assert_eq!(
+            status_filter_params(Some(TaskStatusFilter::Error), false),
+            json!({ "running": false, "statusfilter": ["error"] }),
+        );
+        assert_eq!(
+            status_filter_params(Some(TaskStatusFilter::Warning), true),
+            json!({ "running": false, "statusfilter": ["warning"] }),
+        );
+    }
+}
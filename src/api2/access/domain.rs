@@ -7,7 +7,7 @@ use proxmox_router::{Permission, Router, RpcEnvironment, RpcEnvironmentType, Sub
 use proxmox_schema::api;
 
 use pbs_api_types::{
-    Authid, BasicRealmInfo, Realm, RealmRef, RealmType, PRIV_PERMISSIONS_MODIFY,
+    Authid, BasicRealmInfo, Realm, RealmRef, RealmType, WorkerType, PRIV_PERMISSIONS_MODIFY,
     REMOVE_VANISHED_SCHEMA, UPID_SCHEMA,
 };
 
@@ -95,7 +95,7 @@ pub fn sync_realm(
 ) -> Result<Value, Error> {
     let auth_id: Authid = rpcenv.get_auth_id().unwrap().parse()?;
 
-    let job = Job::new("realm-sync", realm.as_str())
+    let job = Job::new(WorkerType::RealmSync.as_str(), realm.as_str())
         .map_err(|_| format_err!("realm sync already running"))?;
 
     let to_stdout = rpcenv.env_type() == RpcEnvironmentType::CLI;
@@ -36,12 +36,12 @@ use pbs_api_types::{
     print_ns_and_snapshot, print_store_and_ns, Authid, BackupContent, BackupNamespace, BackupType,
     Counts, CryptMode, DataStoreConfig, DataStoreListItem, DataStoreStatus,
     GarbageCollectionJobStatus, GroupListItem, JobScheduleStatus, KeepOptions, Operation,
-    PruneJobOptions, RRDMode, RRDTimeFrame, SnapshotListItem, SnapshotVerifyState,
-    BACKUP_ARCHIVE_NAME_SCHEMA, BACKUP_ID_SCHEMA, BACKUP_NAMESPACE_SCHEMA, BACKUP_TIME_SCHEMA,
-    BACKUP_TYPE_SCHEMA, DATASTORE_SCHEMA, IGNORE_VERIFIED_BACKUPS_SCHEMA, MAX_NAMESPACE_DEPTH,
-    NS_MAX_DEPTH_SCHEMA, PRIV_DATASTORE_AUDIT, PRIV_DATASTORE_BACKUP, PRIV_DATASTORE_MODIFY,
-    PRIV_DATASTORE_PRUNE, PRIV_DATASTORE_READ, PRIV_DATASTORE_VERIFY, UPID, UPID_SCHEMA,
-    VERIFICATION_OUTDATED_AFTER_SCHEMA,
+    PruneJobOptions, RRDMode, RRDTimeFrame, SnapshotListItem, SnapshotVerifyState, VerifyMode,
+    WorkerType, BACKUP_ARCHIVE_NAME_SCHEMA, BACKUP_ID_SCHEMA, BACKUP_NAMESPACE_SCHEMA,
+    BACKUP_TIME_SCHEMA, BACKUP_TYPE_SCHEMA, DATASTORE_SCHEMA, IGNORE_VERIFIED_BACKUPS_SCHEMA,
+    MAX_NAMESPACE_DEPTH, NS_MAX_DEPTH_SCHEMA, PRIV_DATASTORE_AUDIT, PRIV_DATASTORE_BACKUP,
+    PRIV_DATASTORE_MODIFY, PRIV_DATASTORE_PRUNE, PRIV_DATASTORE_READ, PRIV_DATASTORE_VERIFY, UPID,
+    UPID_SCHEMA, VERIFICATION_OUTDATED_AFTER_SCHEMA, VERIFICATION_WORKER_THREADS_SCHEMA,
 };
 use pbs_client::pxar::{create_tar, create_zip};
 use pbs_config::CachedUserInfo;
@@ -65,11 +65,12 @@ use proxmox_rest_server::{formatter, WorkerTask};
 use crate::api2::backup::optional_ns_param;
 use crate::api2::node::rrd::create_value_from_rrd;
 use crate::backup::{
-    check_ns_privs_full, verify_all_backups, verify_backup_dir, verify_backup_group, verify_filter,
-    ListAccessibleBackupGroups, NS_PRIVS_OK,
+    check_ns_privs_full, log_verify_report, verify_all_backups, verify_backup_dir,
+    verify_backup_dir_files, verify_backup_group, verify_filter, ListAccessibleBackupGroups,
+    NS_PRIVS_OK,
 };
 
-use crate::server::jobstate::{compute_schedule_status, Job, JobState};
+use crate::server::jobstate::{compute_randomized_delay, compute_schedule_status, Job, JobState};
 
 const GROUP_NOTES_FILE_NAME: &str = "notes";
 
@@ -108,11 +109,8 @@ fn check_privs_and_load_store(
     Ok(datastore)
 }
 
-fn read_backup_index(
-    backup_dir: &BackupDir,
-) -> Result<(BackupManifest, Vec<BackupContent>), Error> {
-    let (manifest, index_size) = backup_dir.load_manifest()?;
-
+/// List the archives of a manifest, including the manifest itself, with their crypt mode.
+fn manifest_backup_content(manifest: &BackupManifest, index_size: u64) -> Vec<BackupContent> {
     let mut result = Vec::new();
     for item in manifest.files() {
         result.push(BackupContent {
@@ -131,6 +129,14 @@ fn read_backup_index(
         size: Some(index_size),
     });
 
+    result
+}
+
+fn read_backup_index(
+    backup_dir: &BackupDir,
+) -> Result<(BackupManifest, Vec<BackupContent>), Error> {
+    let (manifest, index_size) = backup_dir.load_manifest()?;
+    let result = manifest_backup_content(&manifest, index_size);
     Ok((manifest, result))
 }
 
@@ -765,6 +771,37 @@ pub async fn status(
                 schema: NS_MAX_DEPTH_SCHEMA,
                 optional: true,
             },
+            "worker-threads": {
+                schema: VERIFICATION_WORKER_THREADS_SCHEMA,
+                optional: true,
+            },
+            "dry-run": {
+                type: bool,
+                optional: true,
+                default: false,
+                description: "Only report errors, do not rename corrupt chunks or update the \
+                    verification state of snapshots.",
+            },
+            "recheck-corrupt": {
+                type: bool,
+                optional: true,
+                default: false,
+                description: "Verify chunks already found corrupt again if they got replaced \
+                    in the meantime.",
+            },
+            mode: {
+                type: VerifyMode,
+                optional: true,
+            },
+            "archive-name": {
+                type: Array,
+                optional: true,
+                description: "Only verify these archives of the snapshot. Requires a snapshot \
+                    to be specified.",
+                items: {
+                    schema: BACKUP_ARCHIVE_NAME_SCHEMA,
+                },
+            },
         },
     },
     returns: {
@@ -790,6 +827,11 @@ pub fn verify(
     ignore_verified: Option<bool>,
     outdated_after: Option<i64>,
     max_depth: Option<usize>,
+    worker_threads: Option<usize>,
+    dry_run: bool,
+    recheck_corrupt: bool,
+    mode: Option<VerifyMode>,
+    archive_name: Option<Vec<String>>,
     rpcenv: &mut dyn RpcEnvironment,
 ) -> Result<Value, Error> {
     let auth_id: Authid = rpcenv.get_auth_id().unwrap().parse()?;
@@ -810,7 +852,7 @@ pub fn verify(
 
     let mut backup_dir = None;
     let mut backup_group = None;
-    let mut worker_type = "verify";
+    let mut worker_type = WorkerType::Verify.as_str();
 
     match (backup_type, backup_id, backup_time) {
         (Some(backup_type), Some(backup_id), Some(backup_time)) => {
@@ -831,7 +873,7 @@ pub fn verify(
             }
 
             backup_dir = Some(dir);
-            worker_type = "verify_snapshot";
+            worker_type = WorkerType::VerifySnapshot.as_str();
         }
         (Some(backup_type), Some(backup_id), None) => {
             worker_id = format!(
@@ -849,7 +891,7 @@ pub fn verify(
             }
 
             backup_group = Some(datastore.backup_group(ns.clone(), group));
-            worker_type = "verify_group";
+            worker_type = WorkerType::VerifyGroup.as_str();
         }
         (None, None, None) => {
             worker_id = if ns.is_root() {
@@ -861,6 +903,10 @@ pub fn verify(
         _ => bail!("parameters do not specify a backup group or snapshot"),
     }
 
+    if archive_name.is_some() && backup_dir.is_none() {
+        bail!("parameter 'archive-name' requires a backup snapshot");
+    }
+
     let to_stdout = rpcenv.env_type() == RpcEnvironmentType::CLI;
 
     let upid_str = WorkerTask::new_thread(
@@ -869,15 +915,31 @@ pub fn verify(
         auth_id.to_string(),
         to_stdout,
         move |worker| {
-            let verify_worker = crate::backup::VerifyWorker::new(worker.clone(), datastore);
+            let verify_worker =
+                crate::backup::VerifyWorker::new(worker.clone(), datastore, worker_threads)
+                    .dry_run(dry_run)
+                    .recheck_corrupt(recheck_corrupt)
+                    .mode(mode.unwrap_or_default())
+                    .collect_report(true);
             let failed_dirs = if let Some(backup_dir) = backup_dir {
                 let mut res = Vec::new();
-                if !verify_backup_dir(
-                    &verify_worker,
-                    &backup_dir,
-                    worker.upid().clone(),
-                    Some(&move |manifest| verify_filter(ignore_verified, outdated_after, manifest)),
-                )? {
+                let success = match archive_name {
+                    Some(archive_names) => verify_backup_dir_files(
+                        &verify_worker,
+                        &backup_dir,
+                        &archive_names,
+                        worker.upid().clone(),
+                    )?,
+                    None => verify_backup_dir(
+                        &verify_worker,
+                        &backup_dir,
+                        worker.upid().clone(),
+                        Some(&move |manifest| {
+                            verify_filter(ignore_verified, outdated_after, manifest)
+                        }),
+                    )?,
+                };
+                if !success {
                     res.push(print_ns_and_snapshot(
                         backup_dir.backup_ns(),
                         backup_dir.as_ref(),
@@ -899,15 +961,19 @@ pub fn verify(
                     None
                 };
 
-                verify_all_backups(
+                let (failed_dirs, _) = verify_all_backups(
                     &verify_worker,
                     worker.upid(),
                     ns,
                     max_depth,
                     owner,
                     Some(&move |manifest| verify_filter(ignore_verified, outdated_after, manifest)),
-                )?
+                )?;
+                failed_dirs
             };
+            if let Some(report) = verify_worker.report() {
+                log_verify_report(&*worker, &report);
+            }
             if !failed_dirs.is_empty() {
                 task_log!(worker, "Failed to verify the following snapshots/groups:");
                 for dir in failed_dirs {
@@ -1087,7 +1153,7 @@ pub fn prune(
 
     if param["use-task"].as_bool().unwrap_or(false) {
         let upid = WorkerTask::spawn(
-            "prune",
+            WorkerType::Prune.as_str(),
             Some(worker_id),
             auth_id.to_string(),
             true,
@@ -1098,7 +1164,12 @@ pub fn prune(
         )?;
         Ok(json!(upid))
     } else {
-        let worker = WorkerTask::new("prune", Some(worker_id), auth_id.to_string(), true)?;
+        let worker = WorkerTask::new(
+            WorkerType::Prune.as_str(),
+            Some(worker_id),
+            auth_id.to_string(),
+            true,
+        )?;
         let result = prune_group(worker.clone());
         worker.log_result(&Ok(()));
         Ok(json!(result))
@@ -1157,7 +1228,7 @@ pub fn prune_datastore(
     let to_stdout = rpcenv.env_type() == RpcEnvironmentType::CLI;
 
     let upid_str = WorkerTask::new_thread(
-        "prune",
+        WorkerType::Prune.as_str(),
         Some(worker_id),
         auth_id.to_string(),
         to_stdout,
@@ -1193,7 +1264,7 @@ pub fn start_garbage_collection(
     let datastore = DataStore::lookup_datastore(&store, Some(Operation::Write))?;
     let auth_id: Authid = rpcenv.get_auth_id().unwrap().parse()?;
 
-    let job = Job::new("garbage_collection", &store)
+    let job = Job::new(WorkerType::GarbageCollection.as_str(), &store)
         .map_err(|_| format_err!("garbage collection already running"))?;
 
     let to_stdout = rpcenv.env_type() == RpcEnvironmentType::CLI;
@@ -1284,6 +1355,10 @@ pub fn garbage_collection_status(
         })
         .and_then(|ne| ne);
 
+    if let (Some(next_run), Some(max_delay)) = (info.next_run, &store_config.gc_randomized_delay) {
+        info.next_run = Some(next_run + compute_randomized_delay(&store, max_delay)?);
+    }
+
     info.status = status_in_memory;
 
     Ok(info)
@@ -1536,7 +1611,7 @@ pub fn download_file_decoded(
                 // FIXME: load full blob to verify index checksum?
 
                 Body::wrap_stream(
-                    WrappedReaderStream::new(DataBlobReader::new(file, None)?).map_err(
+                    WrappedReaderStream::new(DataBlobReader::new(file, None, None)?).map_err(
                         move |err| {
                             eprintln!("error during streaming of '{:?}' - {}", path, err);
                             err
@@ -2333,7 +2408,7 @@ pub async fn set_backup_owner(
             );
         }
 
-        backup_group.set_owner(&new_owner, true)?;
+        backup_group.change_owner(&new_owner)?;
 
         Ok(())
     })
@@ -2426,3 +2501,61 @@ const DATASTORE_INFO_ROUTER: Router = Router::new()
 pub const ROUTER: Router = Router::new()
     .get(&API_METHOD_GET_DATASTORE_LIST)
     .match_all("store", &DATASTORE_INFO_ROUTER);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_manifest_backup_content_crypt_mode() -> Result<(), Error> {
+        let snapshot: pbs_api_types::BackupDir = "host/test/2020-01-01T00:00:00Z".parse()?;
+        let mut manifest = BackupManifest::new(snapshot);
+        manifest.add_file(
+            "root.pxar.didx".to_string(),
+            100,
+            [0u8; 32],
+            CryptMode::Encrypt,
+        )?;
+        manifest.add_file(
+            "drive.img.fidx".to_string(),
+            200,
+            [1u8; 32],
+            CryptMode::None,
+        )?;
+        manifest.add_file(
+            "qemu-server.conf.blob".to_string(),
+            3,
+            [2u8; 32],
+            CryptMode::SignOnly,
+        )?;
+
+        let content = manifest_backup_content(&manifest, 42);
+        let modes: Vec<(&str, Option<CryptMode>, Option<u64>)> = content
+            .iter()
+            .map(|c| (c.filename.as_str(), c.crypt_mode, c.size))
+            .collect();
+        assert_eq!(
+            modes,
+            vec![
+                ("root.pxar.didx", Some(CryptMode::Encrypt), Some(100)),
+                ("drive.img.fidx", Some(CryptMode::None), Some(200)),
+                ("qemu-server.conf.blob", Some(CryptMode::SignOnly), Some(3)),
+                (MANIFEST_BLOB_NAME, Some(CryptMode::None), Some(42)),
+            ]
+        );
+
+        // the manifest itself is reported as signed once it carries a signature
+        manifest.signature = Some("signature".to_string());
+        let content = manifest_backup_content(&manifest, 42);
+        assert_eq!(
+            content.last().unwrap().crypt_mode,
+            Some(CryptMode::SignOnly)
+        );
+
+        // the file list API keeps the kebab-case field name
+        let value = serde_json::to_value(&content[0])?;
+        assert_eq!(value["crypt-mode"], "encrypt");
+
+        Ok(())
+    }
+}
@@ -10,8 +10,8 @@ use proxmox_schema::api;
 use proxmox_sortable_macro::sortable;
 
 use pbs_api_types::{
-    Authid, PruneJobConfig, PruneJobStatus, DATASTORE_SCHEMA, JOB_ID_SCHEMA, PRIV_DATASTORE_AUDIT,
-    PRIV_DATASTORE_MODIFY,
+    Authid, PruneJobConfig, PruneJobStatus, WorkerType, DATASTORE_SCHEMA, JOB_ID_SCHEMA,
+    PRIV_DATASTORE_AUDIT, PRIV_DATASTORE_MODIFY,
 };
 use pbs_config::prune;
 use pbs_config::CachedUserInfo;
@@ -119,7 +119,7 @@ pub fn run_prune_job(
 
     user_info.check_privs(&auth_id, &prune_job.acl_path(), PRIV_DATASTORE_MODIFY, true)?;
 
-    let job = Job::new("prunejob", &id)?;
+    let job = Job::new(WorkerType::PruneJob.as_str(), &id)?;
 
     let upid_str = do_prune_job(job, prune_job.options, prune_job.store, &auth_id, None)?;
 
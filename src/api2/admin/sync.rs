@@ -10,7 +10,9 @@ use proxmox_router::{
 use proxmox_schema::api;
 use proxmox_sortable_macro::sortable;
 
-use pbs_api_types::{Authid, SyncJobConfig, SyncJobStatus, DATASTORE_SCHEMA, JOB_ID_SCHEMA};
+use pbs_api_types::{
+    Authid, SyncJobConfig, SyncJobStatus, WorkerType, DATASTORE_SCHEMA, JOB_ID_SCHEMA,
+};
 use pbs_config::sync;
 use pbs_config::CachedUserInfo;
 
@@ -112,7 +114,7 @@ pub fn run_sync_job(
         bail!("permission check failed");
     }
 
-    let job = Job::new("syncjob", &id)?;
+    let job = Job::new(WorkerType::SyncJob.as_str(), &id)?;
 
     let to_stdout = rpcenv.env_type() == RpcEnvironmentType::CLI;
 
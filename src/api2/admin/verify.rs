@@ -11,15 +11,15 @@ use proxmox_schema::api;
 use proxmox_sortable_macro::sortable;
 
 use pbs_api_types::{
-    Authid, VerificationJobConfig, VerificationJobStatus, DATASTORE_SCHEMA, JOB_ID_SCHEMA,
-    PRIV_DATASTORE_AUDIT, PRIV_DATASTORE_VERIFY,
+    Authid, VerificationJobConfig, VerificationJobStatus, WorkerType, DATASTORE_SCHEMA,
+    JOB_ID_SCHEMA, PRIV_DATASTORE_AUDIT, PRIV_DATASTORE_VERIFY,
 };
 use pbs_config::verify;
 use pbs_config::CachedUserInfo;
 
 use crate::server::{
     do_verification_job,
-    jobstate::{compute_schedule_status, Job, JobState},
+    jobstate::{compute_randomized_delay, compute_schedule_status, Job, JobState},
 };
 
 #[api(
@@ -76,7 +76,10 @@ pub fn list_verification_jobs(
         let last_state = JobState::load("verificationjob", &job.id)
             .map_err(|err| format_err!("could not open statefile for {}: {}", &job.id, err))?;
 
-        let status = compute_schedule_status(&last_state, job.schedule.as_deref())?;
+        let mut status = compute_schedule_status(&last_state, job.schedule.as_deref())?;
+        if let (Some(next_run), Some(max_delay)) = (status.next_run, &job.randomized_delay) {
+            status.next_run = Some(next_run + compute_randomized_delay(&job.id, max_delay)?);
+        }
 
         list.push(VerificationJobStatus {
             config: job,
@@ -121,7 +124,7 @@ pub fn run_verification_job(
         true,
     )?;
 
-    let job = Job::new("verificationjob", &id)?;
+    let job = Job::new(WorkerType::VerificationJob.as_str(), &id)?;
     let to_stdout = rpcenv.env_type() == RpcEnvironmentType::CLI;
 
     let upid_str = do_verification_job(job, verification_job, &auth_id, None, to_stdout)?;
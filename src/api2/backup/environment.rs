@@ -2,6 +2,7 @@ use anyhow::{bail, format_err, Error};
 use nix::dir::Dir;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use ::serde::Serialize;
 use serde_json::{json, Value};
@@ -9,7 +10,7 @@ use serde_json::{json, Value};
 use proxmox_router::{RpcEnvironment, RpcEnvironmentType};
 use proxmox_sys::fs::{lock_dir_noblock_shared, replace_file, CreateOptions};
 
-use pbs_api_types::Authid;
+use pbs_api_types::{Authid, WorkerType};
 use pbs_datastore::backup_info::{BackupDir, BackupInfo};
 use pbs_datastore::dynamic_index::DynamicIndexWriter;
 use pbs_datastore::fixed_index::FixedIndexWriter;
@@ -26,6 +27,11 @@ struct UploadStatistic {
     size: u64,
     compressed_size: u64,
     duplicates: u64,
+    // size and compressed size of chunks not already in the datastore
+    #[serde(skip)]
+    stored_size: u64,
+    #[serde(skip)]
+    stored_compressed_size: u64,
 }
 
 impl UploadStatistic {
@@ -35,8 +41,45 @@ impl UploadStatistic {
             size: 0,
             compressed_size: 0,
             duplicates: 0,
+            stored_size: 0,
+            stored_compressed_size: 0,
         }
     }
+
+    fn record_chunk(&mut self, size: u32, compressed_size: u32, is_duplicate: bool) {
+        self.count += 1;
+        self.size += size as u64;
+        self.compressed_size += compressed_size as u64;
+        if is_duplicate {
+            self.duplicates += 1;
+        } else {
+            self.stored_size += size as u64;
+            self.stored_compressed_size += compressed_size as u64;
+        }
+    }
+
+    /// Efficiency summary of a backup with a total size of `backup_size` bytes.
+    ///
+    /// The deduplication ratio relates the backup size to the data which was not yet in the
+    /// datastore, the compression ratio is taken over that newly stored data. Ratios are `null`
+    /// if nothing was stored.
+    fn summary(&self, backup_size: u64) -> Value {
+        let ratio = |a: u64, b: u64| {
+            if b > 0 {
+                Some(a as f64 / b as f64)
+            } else {
+                None
+            }
+        };
+
+        json!({
+            "backup-size": backup_size,
+            "uploaded-bytes": self.size,
+            "stored-bytes": self.stored_compressed_size,
+            "dedup-ratio": ratio(backup_size, self.stored_size),
+            "compression-ratio": ratio(self.stored_size, self.stored_compressed_size),
+        })
+    }
 }
 
 impl std::ops::Add for UploadStatistic {
@@ -48,10 +91,42 @@ impl std::ops::Add for UploadStatistic {
             size: self.size + other.size,
             compressed_size: self.compressed_size + other.compressed_size,
             duplicates: self.duplicates + other.duplicates,
+            stored_size: self.stored_size + other.stored_size,
+            stored_compressed_size: self.stored_compressed_size + other.stored_compressed_size,
         }
     }
 }
 
+/// Benchmark specific counters, logged together with the upload statistics of the backup at
+/// the end of a benchmark. Only the total elapsed time is measured, not single phases.
+struct BenchmarkMetrics {
+    start_time: Instant,
+    closed_archives: u64,
+    speedtest_bytes: u64,
+}
+
+impl BenchmarkMetrics {
+    fn new() -> Self {
+        Self {
+            start_time: Instant::now(),
+            closed_archives: 0,
+            speedtest_bytes: 0,
+        }
+    }
+
+    /// Extends the [`UploadStatistic::summary`] of the benchmark backup with the chunk counts,
+    /// the benchmark counters and the time elapsed since the start of the benchmark.
+    fn summary(&self, upload_stat: &UploadStatistic, backup_size: u64, elapsed: f64) -> Value {
+        let mut summary = upload_stat.summary(backup_size);
+        summary["chunks"] = upload_stat.count.into();
+        summary["duplicates"] = upload_stat.duplicates.into();
+        summary["closed-archives"] = self.closed_archives.into();
+        summary["speedtest-bytes"] = self.speedtest_bytes.into();
+        summary["elapsed"] = elapsed.into();
+        summary
+    }
+}
+
 struct DynamicWriterState {
     name: String,
     index: DynamicIndexWriter,
@@ -83,6 +158,7 @@ struct SharedBackupState {
     known_chunks: KnownChunksMap,
     backup_size: u64, // sums up size of all files
     backup_stat: UploadStatistic,
+    benchmark: Option<BenchmarkMetrics>,
 }
 
 impl SharedBackupState {
@@ -133,6 +209,7 @@ impl BackupEnvironment {
             known_chunks: HashMap::new(),
             backup_size: 0,
             backup_stat: UploadStatistic::new(),
+            benchmark: None,
         };
 
         Self {
@@ -204,12 +281,8 @@ impl BackupEnvironment {
         }
 
         // record statistics
-        data.upload_stat.count += 1;
-        data.upload_stat.size += size as u64;
-        data.upload_stat.compressed_size += compressed_size as u64;
-        if is_duplicate {
-            data.upload_stat.duplicates += 1;
-        }
+        data.upload_stat
+            .record_chunk(size, compressed_size, is_duplicate);
 
         // register chunk
         state.known_chunks.insert(digest, size);
@@ -239,12 +312,8 @@ impl BackupEnvironment {
         };
 
         // record statistics
-        data.upload_stat.count += 1;
-        data.upload_stat.size += size as u64;
-        data.upload_stat.compressed_size += compressed_size as u64;
-        if is_duplicate {
-            data.upload_stat.duplicates += 1;
-        }
+        data.upload_stat
+            .record_chunk(size, compressed_size, is_duplicate);
 
         // register chunk
         state.known_chunks.insert(digest, size);
@@ -252,6 +321,36 @@ impl BackupEnvironment {
         Ok(())
     }
 
+    /// Start collecting benchmark statistics, see `log_benchmark_summary()`.
+    pub fn enable_benchmark_metrics(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.benchmark = Some(BenchmarkMetrics::new());
+    }
+
+    /// Record data received by the upload speed test.
+    pub fn record_speedtest_upload(&self, bytes: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(metrics) = &mut state.benchmark {
+            metrics.speedtest_bytes += bytes;
+        }
+    }
+
+    /// Log the collected benchmark statistics, if enabled.
+    pub fn log_benchmark_summary(&self) {
+        let summary = {
+            let state = self.state.lock().unwrap();
+            match &state.benchmark {
+                Some(metrics) => metrics.summary(
+                    &state.backup_stat,
+                    state.backup_size,
+                    metrics.start_time.elapsed().as_secs_f64(),
+                ),
+                None => return,
+            }
+        };
+        self.log(format!("benchmark summary: {summary}"));
+    }
+
     pub fn lookup_chunk(&self, digest: &[u8; 32]) -> Option<u32> {
         let state = self.state.lock().unwrap();
 
@@ -485,6 +584,9 @@ impl BackupEnvironment {
         state.file_counter += 1;
         state.backup_size += size;
         state.backup_stat = state.backup_stat + data.upload_stat;
+        if let Some(metrics) = &mut state.benchmark {
+            metrics.closed_archives += 1;
+        }
 
         Ok(())
     }
@@ -559,6 +661,9 @@ impl BackupEnvironment {
         state.file_counter += 1;
         state.backup_size += size;
         state.backup_stat = state.backup_stat + data.upload_stat;
+        if let Some(metrics) = &mut state.benchmark {
+            metrics.closed_archives += 1;
+        }
 
         Ok(())
     }
@@ -591,7 +696,9 @@ impl BackupEnvironment {
     }
 
     /// Mark backup as finished
-    pub fn finish_backup(&self) -> Result<(), Error> {
+    ///
+    /// Returns the upload summary, see [`UploadStatistic::summary`].
+    pub fn finish_backup(&self) -> Result<Value, Error> {
         let mut state = self.state.lock().unwrap();
 
         state.ensure_unfinished()?;
@@ -605,6 +712,15 @@ impl BackupEnvironment {
             bail!("backup does not contain valid files (file count == 0)");
         }
 
+        // the server has no key to verify the manifest signature, only reject malformed fields
+        let (manifest, _) = self
+            .backup_dir
+            .load_manifest()
+            .map_err(|err| format_err!("unable to load manifest blob - {}", err))?;
+        manifest
+            .check_signed_fields()
+            .map_err(|err| format_err!("unable to finish backup - {}", err))?;
+
         // check for valid manifest and store stats
         let stats = serde_json::to_value(state.backup_stat)?;
         self.backup_dir
@@ -628,7 +744,29 @@ impl BackupEnvironment {
         // marks the backup as successful
         state.finished = true;
 
-        Ok(())
+        let summary = state.backup_stat.summary(state.backup_size);
+        drop(state);
+
+        self.log_backup_summary(&summary);
+
+        Ok(summary)
+    }
+
+    fn log_backup_summary(&self, summary: &Value) {
+        let format_ratio = |ratio: &Value| match ratio.as_f64() {
+            Some(ratio) => format!("{:.2}", ratio),
+            None => String::from("-"),
+        };
+
+        self.log(format!(
+            "Backup size: {}, uploaded: {}, stored: {}",
+            summary["backup-size"], summary["uploaded-bytes"], summary["stored-bytes"],
+        ));
+        self.log(format!(
+            "Deduplication ratio: {}, compression ratio: {}",
+            format_ratio(&summary["dedup-ratio"]),
+            format_ratio(&summary["compression-ratio"]),
+        ));
     }
 
     /// If verify-new is set on the datastore, this will run a new verify task
@@ -662,14 +800,15 @@ impl BackupEnvironment {
         let backup_dir = self.backup_dir.clone();
 
         WorkerTask::new_thread(
-            "verify",
+            WorkerType::Verify.as_str(),
             Some(worker_id),
             self.auth_id.to_string(),
             false,
             move |worker| {
                 worker.log_message("Automatically verifying newly added snapshot");
 
-                let verify_worker = crate::backup::VerifyWorker::new(worker.clone(), datastore);
+                let verify_worker =
+                    crate::backup::VerifyWorker::new(worker.clone(), datastore, None);
                 if !verify_backup_dir_with_lock(
                     &verify_worker,
                     &backup_dir,
@@ -763,3 +902,65 @@ impl AsRef<BackupEnvironment> for Box<dyn RpcEnvironment> {
         self.as_any().downcast_ref::<BackupEnvironment>().unwrap()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_metrics() {
+        let mut stat = UploadStatistic::new();
+        stat.record_chunk(4096, 1024, false);
+        stat.record_chunk(4096, 1024, true);
+        stat.record_chunk(2048, 2048, false);
+
+        let mut metrics = BenchmarkMetrics::new();
+        metrics.closed_archives += 1;
+        metrics.speedtest_bytes += 1000;
+
+        let summary = metrics.summary(&stat, 10240, 2.0);
+        assert_eq!(summary["chunks"], 3);
+        assert_eq!(summary["duplicates"], 1);
+        assert_eq!(summary["uploaded-bytes"], 10240);
+        assert_eq!(summary["stored-bytes"], 3072);
+        assert_eq!(summary["dedup-ratio"], 10240.0 / 6144.0);
+        assert_eq!(summary["compression-ratio"], 2.0);
+        assert_eq!(summary["closed-archives"], 1);
+        assert_eq!(summary["speedtest-bytes"], 1000);
+        assert_eq!(summary["elapsed"], 2.0);
+
+        // same ratio convention as the upload statistics
+        let empty = BenchmarkMetrics::new().summary(&UploadStatistic::new(), 0, 0.0);
+        assert!(empty["dedup-ratio"].is_null());
+        assert!(empty["compression-ratio"].is_null());
+    }
+
+    #[test]
+    fn test_upload_statistic_summary() {
+        let mut stat = UploadStatistic::new();
+        stat.record_chunk(4096, 1024, false);
+        stat.record_chunk(4096, 1024, true);
+
+        let mut other = UploadStatistic::new();
+        other.record_chunk(4096, 2048, false);
+
+        let stat = stat + other;
+        assert_eq!(stat.count, 3);
+        assert_eq!(stat.duplicates, 1);
+
+        let summary = stat.summary(32768);
+        assert_eq!(summary["backup-size"], 32768);
+        assert_eq!(summary["uploaded-bytes"], 12288);
+        assert_eq!(summary["stored-bytes"], 3072);
+        assert_eq!(summary["dedup-ratio"], 4.0);
+        assert_eq!(summary["compression-ratio"], 8192.0 / 3072.0);
+
+        let empty = UploadStatistic::new().summary(0);
+        assert!(empty["dedup-ratio"].is_null());
+        assert!(empty["compression-ratio"].is_null());
+
+        // the new fields are not part of the manifest statistics
+        let value = serde_json::to_value(stat).unwrap();
+        assert!(value.get("stored_size").is_none());
+    }
+}
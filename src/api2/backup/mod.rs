@@ -2,7 +2,6 @@
 
 use anyhow::{bail, format_err, Error};
 use futures::*;
-use hex::FromHex;
 use hyper::header::{HeaderValue, CONNECTION, UPGRADE};
 use hyper::http::request::Parts;
 use hyper::{Body, Request, Response, StatusCode};
@@ -17,12 +16,14 @@ use proxmox_schema::*;
 use proxmox_sortable_macro::sortable;
 
 use pbs_api_types::{
-    Authid, BackupNamespace, BackupType, Operation, SnapshotVerifyState, VerifyState,
+    Authid, BackupNamespace, BackupType, Operation, SnapshotVerifyState, VerifyState, WorkerType,
     BACKUP_ARCHIVE_NAME_SCHEMA, BACKUP_ID_SCHEMA, BACKUP_NAMESPACE_SCHEMA, BACKUP_TIME_SCHEMA,
     BACKUP_TYPE_SCHEMA, CHUNK_DIGEST_SCHEMA, DATASTORE_SCHEMA, PRIV_DATASTORE_BACKUP,
 };
 use pbs_config::CachedUserInfo;
-use pbs_datastore::index::IndexFile;
+use pbs_datastore::chunk_store::verify_chunk_size;
+use pbs_datastore::file_formats::{ENCRYPTED_BLOB_MAGIC_1_0, ENCR_COMPR_BLOB_MAGIC_1_0};
+use pbs_datastore::index::{parse_digest, IndexFile};
 use pbs_datastore::manifest::{archive_type, ArchiveType};
 use pbs_datastore::{DataStore, PROXMOX_BACKUP_PROTOCOL_ID_V1};
 use pbs_tools::json::{required_array_param, required_integer_param, required_string_param};
@@ -66,6 +67,22 @@ pub(crate) fn optional_ns_param(param: &Value) -> Result<BackupNamespace, Error>
     }
 }
 
+/// Reject backup times too far ahead of the server time.
+///
+/// New snapshots must be newer than the last one of the group, so a snapshot dated in the future
+/// (e.g. by a client with a wrong clock) would block all further backups of the group.
+fn check_backup_time_skew(backup_time: i64, now: i64, max_skew: i64) -> Result<(), Error> {
+    let skew = backup_time - now;
+    if skew > max_skew {
+        bail!(
+            "backup time lies {}s ahead of the server time (allowed skew is {}s) - check the client clock",
+            skew,
+            max_skew,
+        );
+    }
+    Ok(())
+}
+
 fn upgrade_to_backup_protocol(
     parts: Parts,
     req_body: Body,
@@ -130,14 +147,20 @@ fn upgrade_to_backup_protocol(
             if !benchmark {
                 bail!("unable to run benchmark without --benchmark flags");
             }
-            "benchmark"
+            WorkerType::Benchmark
         } else {
             if benchmark {
                 bail!("benchmark flags is only allowed on 'host/benchmark'");
             }
-            "backup"
+            WorkerType::Backup
         };
 
+        check_backup_time_skew(
+            backup_dir_arg.time,
+            proxmox_time::epoch_i64(),
+            datastore.max_backup_time_skew(),
+        )?;
+
         // lock backup group to only allow one backup per group at a time
         let (owner, _group_guard) = datastore.create_locked_backup_group(
             backup_group.backup_ns(),
@@ -148,7 +171,7 @@ fn upgrade_to_backup_protocol(
         // permission check
         let correct_owner =
             owner == auth_id || (owner.is_token() && Authid::from(owner.user().clone()) == auth_id);
-        if !correct_owner && worker_type != "benchmark" {
+        if !correct_owner && worker_type != WorkerType::Benchmark {
             // only the owner is allowed to create additional snapshots
             bail!("backup owner check failed ({} != {})", auth_id, owner);
         }
@@ -198,7 +221,7 @@ fn upgrade_to_backup_protocol(
         }
 
         WorkerTask::spawn(
-            worker_type,
+            worker_type.as_str(),
             Some(worker_id),
             auth_id.to_string(),
             true,
@@ -213,6 +236,9 @@ fn upgrade_to_backup_protocol(
 
                 env.debug = debug;
                 env.last_backup = last_backup;
+                if benchmark {
+                    env.enable_benchmark_metrics();
+                }
 
                 let origin = match rpcenv.get_client_ip().map(|addr| addr.ip()) {
                     Some(ip) => format!(" from {ip}"),
@@ -236,10 +262,10 @@ fn upgrade_to_backup_protocol(
 
                         let mut http = hyper::server::conn::Http::new();
                         http.http2_only(true);
-                        // increase window size: todo - find optiomal size
-                        let window_size = 32 * 1024 * 1024; // max = (1 << 31) - 2
-                        http.http2_initial_stream_window_size(window_size);
-                        http.http2_initial_connection_window_size(window_size);
+                        let (stream_window_size, connection_window_size) =
+                            crate::api2::helpers::h2_window_sizes();
+                        http.http2_initial_stream_window_size(stream_window_size);
+                        http.http2_initial_connection_window_size(connection_window_size);
                         http.http2_max_frame_size(4 * 1024 * 1024);
 
                         let env3 = env2.clone();
@@ -273,6 +299,7 @@ fn upgrade_to_backup_protocol(
                         abrt = abort_future => abrt,
                     };
                     if benchmark {
+                        env.log_benchmark_summary();
                         env.log("benchmark finished successfully");
                         proxmox_async::runtime::block_in_place(|| env.remove_backup())?;
                         return Ok(());
@@ -367,6 +394,10 @@ const BACKUP_API_SUBDIRS: SubdirMap = &[
             .post(&API_METHOD_CREATE_FIXED_INDEX)
             .put(&API_METHOD_FIXED_APPEND),
     ),
+    (
+        "known_chunks",
+        &Router::new().post(&API_METHOD_KNOWN_CHUNKS),
+    ),
     (
         "previous",
         &Router::new().download(&API_METHOD_DOWNLOAD_PREVIOUS),
@@ -377,7 +408,9 @@ const BACKUP_API_SUBDIRS: SubdirMap = &[
     ),
     (
         "speedtest",
-        &Router::new().upload(&API_METHOD_UPLOAD_SPEEDTEST),
+        &Router::new()
+            .download(&API_METHOD_DOWNLOAD_SPEEDTEST)
+            .upload(&API_METHOD_UPLOAD_SPEEDTEST),
     ),
 ];
 
@@ -431,6 +464,15 @@ pub const API_METHOD_CREATE_FIXED_INDEX: ApiMethod = ApiMethod::new(
                 false,
                 &IntegerSchema::new("File size.").minimum(1).schema()
             ),
+            (
+                "chunk-size",
+                true,
+                &IntegerSchema::new("Chunk size in bytes, must be a power of two.")
+                    .minimum(64 * 1024)
+                    .maximum(4096 * 1024)
+                    .default(4096 * 1024)
+                    .schema()
+            ),
             (
                 "reuse-csum",
                 true,
@@ -463,7 +505,8 @@ fn create_fixed_index(
     let mut path = env.backup_dir.relative_path();
     path.push(&archive_name);
 
-    let chunk_size = 4096 * 1024; // todo: ??
+    let chunk_size = param["chunk-size"].as_u64().unwrap_or(4096 * 1024) as usize;
+    verify_chunk_size(chunk_size)?;
 
     // do incremental backup if csum is set
     let mut reader = None;
@@ -487,6 +530,14 @@ fn create_fixed_index(
             }
         };
 
+        if index.chunk_size != chunk_size {
+            bail!(
+                "cannot reuse index - chunk size ({}) doesn't match last backup's ({})",
+                chunk_size,
+                index.chunk_size
+            );
+        }
+
         let (old_csum, _) = index.compute_csum();
         let old_csum = hex::encode(old_csum);
         if old_csum != csum {
@@ -570,7 +621,7 @@ fn dynamic_append(
 
     for (i, item) in digest_list.iter().enumerate() {
         let digest_str = item.as_str().unwrap();
-        let digest = <[u8; 32]>::from_hex(digest_str)?;
+        let digest = parse_digest(digest_str)?;
         let offset = offset_list[i].as_u64().unwrap();
         let size = env
             .lookup_chunk(&digest)
@@ -644,7 +695,7 @@ fn fixed_append(
 
     for (i, item) in digest_list.iter().enumerate() {
         let digest_str = item.as_str().unwrap();
-        let digest = <[u8; 32]>::from_hex(digest_str)?;
+        let digest = parse_digest(digest_str)?;
         let offset = offset_list[i].as_u64().unwrap();
         let size = env
             .lookup_chunk(&digest)
@@ -711,7 +762,7 @@ fn close_dynamic_index(
     let chunk_count = required_integer_param(&param, "chunk-count")? as u64;
     let size = required_integer_param(&param, "size")? as u64;
     let csum_str = required_string_param(&param, "csum")?;
-    let csum = <[u8; 32]>::from_hex(csum_str)?;
+    let csum = parse_digest(csum_str)?;
 
     let env: &BackupEnvironment = rpcenv.as_ref();
 
@@ -764,7 +815,7 @@ fn close_fixed_index(
     let chunk_count = required_integer_param(&param, "chunk-count")? as u64;
     let size = required_integer_param(&param, "size")? as u64;
     let csum_str = required_string_param(&param, "csum")?;
-    let csum = <[u8; 32]>::from_hex(csum_str)?;
+    let csum = parse_digest(csum_str)?;
 
     let env: &BackupEnvironment = rpcenv.as_ref();
 
@@ -782,10 +833,120 @@ fn finish_backup(
 ) -> Result<Value, Error> {
     let env: &BackupEnvironment = rpcenv.as_ref();
 
-    env.finish_backup()?;
+    let summary = env.finish_backup()?;
     env.log("successfully finished backup");
 
-    Ok(Value::Null)
+    Ok(summary)
+}
+
+#[sortable]
+pub const API_METHOD_KNOWN_CHUNKS: ApiMethod = ApiMethod::new(
+    &ApiHandler::Sync(&known_chunks),
+    &ObjectSchema::new(
+        "Register encrypted chunks already present in the datastore, so that they need not be \
+        uploaded. Returns the list of registered digests. Unencrypted chunks are never reported \
+        as known, they always need to be uploaded.",
+        &sorted!([
+            (
+                "digest-list",
+                false,
+                &ArraySchema::new("Chunk digest list.", &CHUNK_DIGEST_SCHEMA).schema()
+            ),
+            (
+                "size-list",
+                false,
+                &ArraySchema::new(
+                    "Chunk size list.",
+                    &IntegerSchema::new("Corresponding chunk sizes.")
+                        .minimum(1)
+                        .maximum(16 * 1024 * 1024)
+                        .schema()
+                )
+                .schema()
+            ),
+        ]),
+    ),
+);
+
+/// Check if the chunk file at `path` exists and contains an encrypted chunk.
+///
+/// Only encrypted chunks may be registered without uploading them, as their digest depends on
+/// the encryption key. The digest of an unencrypted chunk is a plain SHA-256 of its data, so
+/// accepting those would allow probing for and referencing data from other backups.
+fn is_encrypted_chunk_file(path: &std::path::Path) -> Result<bool, Error> {
+    use std::io::Read;
+
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => bail!("unable to open chunk {path:?} - {err}"),
+    };
+
+    let mut magic = [0u8; 8];
+    if file.read_exact(&mut magic).is_err() {
+        return Ok(false); // empty or truncated chunk, needs to be uploaded again
+    }
+
+    Ok(magic == ENCRYPTED_BLOB_MAGIC_1_0 || magic == ENCR_COMPR_BLOB_MAGIC_1_0)
+}
+
+fn known_chunks(
+    param: Value,
+    _info: &ApiMethod,
+    rpcenv: &mut dyn RpcEnvironment,
+) -> Result<Value, Error> {
+    let digest_list = required_array_param(&param, "digest-list")?;
+    let size_list = required_array_param(&param, "size-list")?;
+
+    if size_list.len() != digest_list.len() {
+        bail!(
+            "size list has wrong length ({} != {})",
+            size_list.len(),
+            digest_list.len()
+        );
+    }
+
+    let env: &BackupEnvironment = rpcenv.as_ref();
+
+    let mut chunks = Vec::with_capacity(digest_list.len());
+    for (item, size) in digest_list.iter().zip(size_list) {
+        let digest = parse_digest(item.as_str().unwrap())?;
+        chunks.push((digest, size.as_u64().unwrap() as u32));
+    }
+
+    let known = register_known_chunks(&env.datastore, chunks, |digest, size| {
+        env.register_chunk(digest, size)
+    })?;
+
+    env.debug(format!(
+        "known_chunks: {} of {} chunks registered",
+        known.len(),
+        digest_list.len()
+    ));
+
+    Ok(known.iter().map(hex::encode).collect())
+}
+
+/// Call `register` for every chunk which is present as encrypted chunk file in `datastore`.
+///
+/// Returns the digests of the registered chunks.
+fn register_known_chunks(
+    datastore: &DataStore,
+    chunks: Vec<([u8; 32], u32)>,
+    mut register: impl FnMut([u8; 32], u32) -> Result<(), Error>,
+) -> Result<Vec<[u8; 32]>, Error> {
+    let mut known = Vec::new();
+
+    for (digest, size) in chunks {
+        let (path, _) = datastore.chunk_path(&digest);
+        // touch it, so that a concurrent garbage collection keeps the chunk
+        if is_encrypted_chunk_file(&path)? && datastore.cond_touch_chunk(&digest, false)? {
+            register(digest, size)?;
+            known.push(digest);
+        }
+    }
+
+    Ok(known)
 }
 
 #[sortable]
@@ -869,3 +1030,102 @@ fn download_previous(
     }
     .boxed()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_encrypted_chunk_file() -> Result<(), Error> {
+        let mut path = std::fs::canonicalize(".")?; // we need absolute path
+        path.push(".testdir-known-chunks");
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path)?;
+
+        let data = vec![7u8; 4096];
+        let crypt_config = pbs_tools::crypt_config::CryptConfig::new([1u8; 32])?;
+
+        let encrypted = path.join("encrypted");
+        let blob = pbs_datastore::DataBlob::encode(&data, Some(&crypt_config), true)?;
+        std::fs::write(&encrypted, blob.raw_data())?;
+
+        let plain = path.join("plain");
+        let blob = pbs_datastore::DataBlob::encode(&data, None, true)?;
+        std::fs::write(&plain, blob.raw_data())?;
+
+        let empty = path.join("empty");
+        std::fs::write(&empty, b"")?;
+
+        assert!(is_encrypted_chunk_file(&encrypted)?);
+        assert!(!is_encrypted_chunk_file(&plain)?);
+        assert!(!is_encrypted_chunk_file(&empty)?);
+        assert!(!is_encrypted_chunk_file(&path.join("missing"))?);
+
+        let _ = std::fs::remove_dir_all(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_known_chunks() -> Result<(), Error> {
+        use pbs_api_types::DatastoreFSyncLevel;
+        use pbs_datastore::ChunkStore;
+
+        let mut path = std::fs::canonicalize(".")?; // we need absolute path
+        path.push(".testdir-register-known-chunks");
+        let _ = std::fs::remove_dir_all(&path);
+
+        ChunkStore::create(
+            "test",
+            &path,
+            nix::unistd::Uid::current(),
+            nix::unistd::Gid::current(),
+            None,
+            DatastoreFSyncLevel::None,
+        )?;
+        let datastore = unsafe { DataStore::open_path("test", &path, None)? };
+
+        let crypt_config = pbs_tools::crypt_config::CryptConfig::new([1u8; 32])?;
+
+        let data = vec![7u8; 4096];
+        let encrypted = crypt_config.compute_digest(&data);
+        let blob = pbs_datastore::DataBlob::encode(&data, Some(&crypt_config), true)?;
+        datastore.insert_chunk(&blob, &encrypted)?;
+
+        let data = vec![8u8; 4096];
+        let plain = openssl::sha::sha256(&data);
+        let blob = pbs_datastore::DataBlob::encode(&data, None, true)?;
+        datastore.insert_chunk(&blob, &plain)?;
+
+        let missing = [9u8; 32];
+
+        let mut registered = Vec::new();
+        let known = register_known_chunks(
+            &datastore,
+            vec![(plain, 4096), (encrypted, 4096), (missing, 4096)],
+            |digest, size| {
+                registered.push((digest, size));
+                Ok(())
+            },
+        )?;
+
+        assert_eq!(known, vec![encrypted]);
+        assert_eq!(registered, vec![(encrypted, 4096)]);
+
+        drop(datastore);
+        let _ = std::fs::remove_dir_all(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_backup_time_skew() {
+        let now = 1_700_000_000;
+
+        assert!(check_backup_time_skew(now - 3600, now, 300).is_ok());
+        assert!(check_backup_time_skew(now, now, 300).is_ok());
+        assert!(check_backup_time_skew(now + 300, now, 300).is_ok());
+
+        assert!(check_backup_time_skew(now + 301, now, 300).is_err());
+        assert!(check_backup_time_skew(now + 5 * 365 * 86400, now, 300).is_err());
+        assert!(check_backup_time_skew(now + 1, now, 0).is_err());
+    }
+}
@@ -4,17 +4,17 @@ use std::task::{Context, Poll};
 
 use anyhow::{bail, format_err, Error};
 use futures::*;
-use hex::FromHex;
 use hyper::http::request::Parts;
-use hyper::Body;
+use hyper::{header, Body, Response, StatusCode};
 use serde_json::{json, Value};
 
-use proxmox_router::{ApiHandler, ApiMethod, ApiResponseFuture, RpcEnvironment};
+use proxmox_router::{http_err, ApiHandler, ApiMethod, ApiResponseFuture, RpcEnvironment};
 use proxmox_schema::*;
 use proxmox_sortable_macro::sortable;
 
 use pbs_api_types::{BACKUP_ARCHIVE_NAME_SCHEMA, CHUNK_DIGEST_SCHEMA};
 use pbs_datastore::file_formats::{DataBlobHeader, EncryptedDataBlobHeader};
+use pbs_datastore::index::parse_digest;
 use pbs_datastore::{DataBlob, DataStore};
 use pbs_tools::json::{required_integer_param, required_string_param};
 
@@ -106,6 +106,24 @@ impl Future for UploadChunk {
     }
 }
 
+/// Check the announced sizes before reading the chunk body.
+///
+/// Encoding never grows the data by more than the blob header, as uncompressed data is stored
+/// when compression does not pay off, and encryption does not add any padding.
+fn check_announced_chunk_size(size: u32, encoded_size: u32) -> Result<(), Error> {
+    let max_encoded_size = size as usize + std::mem::size_of::<EncryptedDataBlobHeader>();
+    if encoded_size as usize > max_encoded_size {
+        return Err(http_err!(
+            BAD_REQUEST,
+            "encoded chunk size {} exceeds maximum of {} for chunk size {}",
+            encoded_size,
+            max_encoded_size,
+            size
+        ));
+    }
+    Ok(())
+}
+
 #[sortable]
 pub const API_METHOD_UPLOAD_FIXED_CHUNK: ApiMethod = ApiMethod::new(
     &ApiHandler::AsyncHttp(&upload_fixed_chunk),
@@ -155,9 +173,10 @@ fn upload_fixed_chunk(
         let wid = required_integer_param(&param, "wid")? as usize;
         let size = required_integer_param(&param, "size")? as u32;
         let encoded_size = required_integer_param(&param, "encoded-size")? as u32;
+        check_announced_chunk_size(size, encoded_size)?;
 
         let digest_str = required_string_param(&param, "digest")?;
-        let digest = <[u8; 32]>::from_hex(digest_str)?;
+        let digest = parse_digest(digest_str)?;
 
         let env: &BackupEnvironment = rpcenv.as_ref();
 
@@ -224,9 +243,10 @@ fn upload_dynamic_chunk(
         let wid = required_integer_param(&param, "wid")? as usize;
         let size = required_integer_param(&param, "size")? as u32;
         let encoded_size = required_integer_param(&param, "encoded-size")? as u32;
+        check_announced_chunk_size(size, encoded_size)?;
 
         let digest_str = required_string_param(&param, "digest")?;
-        let digest = <[u8; 32]>::from_hex(digest_str)?;
+        let digest = parse_digest(digest_str)?;
 
         let env: &BackupEnvironment = rpcenv.as_ref();
 
@@ -256,6 +276,8 @@ fn upload_speedtest(
     rpcenv: Box<dyn RpcEnvironment>,
 ) -> ApiResponseFuture {
     async move {
+        let start_time = std::time::Instant::now();
+
         let result = req_body
             .map_err(Error::from)
             .try_fold(0, |size: usize, chunk| {
@@ -265,20 +287,91 @@ fn upload_speedtest(
             })
             .await;
 
-        match result {
+        let env: &BackupEnvironment = rpcenv.as_ref();
+        let result = match result {
             Ok(size) => {
                 println!("UPLOAD END {} bytes", size);
+                env.record_speedtest_upload(size as u64);
+                Ok(speedtest_result(size as u64, start_time.elapsed()))
             }
             Err(err) => {
                 println!("Upload error: {}", err);
+                Err(err)
             }
-        }
-        let env: &BackupEnvironment = rpcenv.as_ref();
-        Ok(env.format_response(Ok(Value::Null)))
+        };
+        Ok(env.format_response(result))
+    }
+    .boxed()
+}
+
+/// Builds the speedtest response, the rate is in MiB per second.
+fn speedtest_result(bytes: u64, elapsed: std::time::Duration) -> Value {
+    let elapsed_secs = elapsed.as_secs_f64();
+    let mib_per_sec = if elapsed_secs > 0.0 {
+        (bytes as f64) / (1024.0 * 1024.0) / elapsed_secs
+    } else {
+        0.0
+    };
+
+    json!({
+        "bytes": bytes,
+        "elapsed-secs": elapsed_secs,
+        "mib-per-sec": mib_per_sec,
+    })
+}
+
+/// Maximum size of a single download speedtest response.
+const MAX_SPEEDTEST_DOWNLOAD_SIZE: usize = 64 * 1024 * 1024;
+
+#[sortable]
+pub const API_METHOD_DOWNLOAD_SPEEDTEST: ApiMethod = ApiMethod::new(
+    &ApiHandler::AsyncHttp(&download_speedtest),
+    &ObjectSchema::new(
+        "Test download speed.",
+        &sorted!([(
+            "size",
+            true,
+            &IntegerSchema::new("Number of bytes to download.")
+                .minimum(1)
+                .maximum(MAX_SPEEDTEST_DOWNLOAD_SIZE as isize)
+                .default(1024 * 1024)
+                .schema()
+        ),]),
+    ),
+);
+
+fn download_speedtest(
+    _parts: Parts,
+    _req_body: Body,
+    param: Value,
+    _info: &ApiMethod,
+    _rpcenv: Box<dyn RpcEnvironment>,
+) -> ApiResponseFuture {
+    async move {
+        let size = param["size"].as_u64().unwrap_or(1024 * 1024) as usize;
+
+        let body = Body::from(speedtest_data(size)?);
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .body(body)
+            .unwrap())
     }
     .boxed()
 }
 
+/// Generates random (incompressible) test data, so that compression on the
+/// way does not skew the measured rate.
+fn speedtest_data(size: usize) -> Result<Vec<u8>, Error> {
+    if size > MAX_SPEEDTEST_DOWNLOAD_SIZE {
+        bail!("speedtest size {size} exceeds maximum of {MAX_SPEEDTEST_DOWNLOAD_SIZE} bytes");
+    }
+    let mut data = vec![0u8; size];
+    openssl::rand::rand_bytes(&mut data)?;
+    Ok(data)
+}
+
 #[sortable]
 pub const API_METHOD_UPLOAD_BLOB: ApiMethod = ApiMethod::new(
     &ApiHandler::AsyncHttp(&upload_blob),
@@ -340,3 +433,42 @@ fn upload_blob(
     }
     .boxed()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_announced_chunk_size() {
+        let header_size = std::mem::size_of::<EncryptedDataBlobHeader>() as u32;
+
+        // compressed and exact maximum sizes are fine
+        assert!(check_announced_chunk_size(4096, 100).is_ok());
+        assert!(check_announced_chunk_size(4096, 4096 + header_size).is_ok());
+
+        // over-announced encoded size
+        assert!(check_announced_chunk_size(4096, 4096 + header_size + 1).is_err());
+        assert!(check_announced_chunk_size(1, 16 * 1024 * 1024).is_err());
+    }
+
+    #[test]
+    fn test_speedtest_data() {
+        for size in [1, 4096, 1024 * 1024] {
+            assert_eq!(speedtest_data(size).unwrap().len(), size);
+        }
+        assert!(speedtest_data(MAX_SPEEDTEST_DOWNLOAD_SIZE + 1).is_err());
+    }
+
+    #[test]
+    fn test_speedtest_result() {
+        let result = speedtest_result(64 * 1024 * 1024, std::time::Duration::from_secs(2));
+        assert_eq!(result["bytes"], 64 * 1024 * 1024);
+        assert_eq!(result["elapsed-secs"], 2.0);
+        assert_eq!(result["mib-per-sec"], 32.0);
+
+        // zero elapsed time must not divide by zero
+        let result = speedtest_result(1024, std::time::Duration::ZERO);
+        assert_eq!(result["bytes"], 1024);
+        assert_eq!(result["mib-per-sec"], 0.0);
+    }
+}
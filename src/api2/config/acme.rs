@@ -19,7 +19,7 @@ use proxmox_sys::{task_log, task_warn};
 use proxmox_acme::types::AccountData as AcmeAccountData;
 use proxmox_acme::Account;
 
-use pbs_api_types::{Authid, PRIV_SYS_MODIFY};
+use pbs_api_types::{Authid, WorkerType, PRIV_SYS_MODIFY};
 
 use crate::acme::AcmeClient;
 use crate::api2::types::{AcmeAccountName, AcmeChallengeSchema, KnownAcmeDirectory};
@@ -236,7 +236,7 @@ fn register_account(
     });
 
     WorkerTask::spawn(
-        "acme-register",
+        WorkerType::AcmeRegister.as_str(),
         Some(name.to_string()),
         auth_id.to_string(),
         true,
@@ -305,7 +305,7 @@ pub fn update_account(
     let auth_id: Authid = rpcenv.get_auth_id().unwrap().parse()?;
 
     WorkerTask::spawn(
-        "acme-update",
+        WorkerType::AcmeUpdate.as_str(),
         Some(name.to_string()),
         auth_id.to_string(),
         true,
@@ -350,7 +350,7 @@ pub fn deactivate_account(
     let auth_id: Authid = rpcenv.get_auth_id().unwrap().parse()?;
 
     WorkerTask::spawn(
-        "acme-deactivate",
+        WorkerType::AcmeDeactivate.as_str(),
         Some(name.to_string()),
         auth_id.to_string(),
         true,
@@ -13,8 +13,9 @@ use proxmox_uuid::Uuid;
 
 use pbs_api_types::{
     Authid, DataStoreConfig, DataStoreConfigUpdater, DatastoreNotify, DatastoreTuning, KeepOptions,
-    MaintenanceMode, PruneJobConfig, PruneJobOptions, DATASTORE_SCHEMA, PRIV_DATASTORE_ALLOCATE,
-    PRIV_DATASTORE_AUDIT, PRIV_DATASTORE_MODIFY, PROXMOX_CONFIG_DIGEST_SCHEMA, UPID_SCHEMA,
+    MaintenanceMode, PruneJobConfig, PruneJobOptions, WorkerType, DATASTORE_SCHEMA,
+    PRIV_DATASTORE_ALLOCATE, PRIV_DATASTORE_AUDIT, PRIV_DATASTORE_MODIFY,
+    PROXMOX_CONFIG_DIGEST_SCHEMA, UPID_SCHEMA,
 };
 use pbs_config::BackupLockGuard;
 use pbs_datastore::chunk_store::ChunkStore;
@@ -79,9 +80,10 @@ pub(crate) fn do_create_datastore(
             .parse_property_string(datastore.tuning.as_deref().unwrap_or(""))?,
     )?;
     let backup_user = pbs_config::backup_user()?;
-    let _store = ChunkStore::create(
+    let _store = ChunkStore::create_with_chunk_path(
         &datastore.name,
         path,
+        datastore.chunk_path.as_ref().map(PathBuf::from),
         backup_user.uid,
         backup_user.gid,
         worker,
@@ -151,7 +153,7 @@ pub fn create_datastore(
     };
 
     WorkerTask::new_thread(
-        "create-datastore",
+        WorkerType::CreateDatastore.as_str(),
         Some(config.name.to_string()),
         auth_id.to_string(),
         to_stdout,
@@ -202,6 +204,8 @@ pub enum DeletableProperty {
     Comment,
     /// Delete the garbage collection schedule.
     GcSchedule,
+    /// Delete the garbage collection randomized delay.
+    GcRandomizedDelay,
     /// Delete the prune job schedule.
     PruneSchedule,
     /// Delete the keep-last property
@@ -216,6 +220,8 @@ pub enum DeletableProperty {
     KeepMonthly,
     /// Delete the keep-yearly property
     KeepYearly,
+    /// Delete the keep-last-verified property
+    KeepLastVerified,
     /// Delete the verify-new property
     VerifyNew,
     /// Delete the notify-user property
@@ -287,6 +293,9 @@ pub fn update_datastore(
                 DeletableProperty::GcSchedule => {
                     data.gc_schedule = None;
                 }
+                DeletableProperty::GcRandomizedDelay => {
+                    data.gc_randomized_delay = None;
+                }
                 DeletableProperty::PruneSchedule => {
                     data.prune_schedule = None;
                 }
@@ -308,6 +317,9 @@ pub fn update_datastore(
                 DeletableProperty::KeepYearly => {
                     data.keep.keep_yearly = None;
                 }
+                DeletableProperty::KeepLastVerified => {
+                    data.keep.keep_last_verified = None;
+                }
                 DeletableProperty::VerifyNew => {
                     data.verify_new = None;
                 }
@@ -344,6 +356,9 @@ pub fn update_datastore(
         gc_schedule_changed = data.gc_schedule != update.gc_schedule;
         data.gc_schedule = update.gc_schedule;
     }
+    if update.gc_randomized_delay.is_some() {
+        data.gc_randomized_delay = update.gc_randomized_delay;
+    }
 
     macro_rules! prune_disabled {
         ($(($param:literal, $($member:tt)+)),+) => {
@@ -364,6 +379,7 @@ pub fn update_datastore(
         ("keep-weekly", keep.keep_weekly),
         ("keep-monthly", keep.keep_monthly),
         ("keep-yearly", keep.keep_yearly),
+        ("keep-last-verified", keep.keep_last_verified),
         ("prune-schedule", prune_schedule)
     }
 
@@ -524,7 +540,7 @@ pub async fn delete_datastore(
     let to_stdout = rpcenv.env_type() == RpcEnvironmentType::CLI;
 
     let upid = WorkerTask::new_thread(
-        "delete-datastore",
+        WorkerType::DeleteDatastore.as_str(),
         Some(name.clone()),
         auth_id.to_string(),
         to_stdout,
@@ -170,6 +170,8 @@ pub enum DeletableProperty {
     KeepMonthly,
     /// Delete number of yearly backups to keep.
     KeepYearly,
+    /// Delete number of last verified backups to keep.
+    KeepLastVerified,
 }
 
 #[api(
@@ -261,6 +263,9 @@ pub fn update_prune_job(
                 DeletableProperty::KeepYearly => {
                     data.options.keep.keep_yearly = None;
                 }
+                DeletableProperty::KeepLastVerified => {
+                    data.options.keep.keep_last_verified = None;
+                }
             }
         }
     }
@@ -317,6 +322,9 @@ pub fn update_prune_job(
     if let Some(value) = update.options.keep.keep_yearly {
         data.options.keep.keep_yearly = Some(value);
     }
+    if let Some(value) = update.options.keep.keep_last_verified {
+        data.options.keep.keep_last_verified = Some(value);
+    }
 
     config.set_data(&id, "prune", &data)?;
 
@@ -149,6 +149,14 @@ pub enum DeletableProperty {
     Ns,
     /// Delete max-depth property, defaulting to full recursion again
     MaxDepth,
+    /// Delete worker-threads property, defaulting to the number of available CPUs
+    WorkerThreads,
+    /// Delete randomized-delay property
+    RandomizedDelay,
+    /// Delete read-limit property, do not throttle reading chunks
+    ReadLimit,
+    /// Delete checkpoint-max-age property, do not keep checkpoints
+    CheckpointMaxAge,
 }
 
 #[api(
@@ -229,6 +237,18 @@ pub fn update_verification_job(
                 DeletableProperty::MaxDepth => {
                     data.max_depth = None;
                 }
+                DeletableProperty::WorkerThreads => {
+                    data.worker_threads = None;
+                }
+                DeletableProperty::RandomizedDelay => {
+                    data.randomized_delay = None;
+                }
+                DeletableProperty::ReadLimit => {
+                    data.read_limit = None;
+                }
+                DeletableProperty::CheckpointMaxAge => {
+                    data.checkpoint_max_age = None;
+                }
             }
         }
     }
@@ -266,6 +286,18 @@ pub fn update_verification_job(
             data.max_depth = Some(max_depth);
         }
     }
+    if update.worker_threads.is_some() {
+        data.worker_threads = update.worker_threads;
+    }
+    if update.randomized_delay.is_some() {
+        data.randomized_delay = update.randomized_delay;
+    }
+    if update.read_limit.is_some() {
+        data.read_limit = update.read_limit;
+    }
+    if update.checkpoint_max_age.is_some() {
+        data.checkpoint_max_age = update.checkpoint_max_age;
+    }
 
     // check new store and NS
     user_info.check_privs(&auth_id, &data.acl_path(), PRIV_DATASTORE_VERIFY, true)?;
@@ -27,3 +27,56 @@ pub async fn create_download_response(path: PathBuf) -> Result<Response<Body>, E
         .body(body)
         .unwrap())
 }
+
+/// Default HTTP/2 stream and connection window size for the backup and reader protocol.
+pub const DEFAULT_H2_WINDOW_SIZE: u32 = 32 * 1024 * 1024;
+
+const MIN_H2_WINDOW_SIZE: u32 = 65_535; // HTTP/2 default
+const MAX_H2_WINDOW_SIZE: u32 = (1 << 31) - 2;
+
+/// Parse a HTTP/2 window size, clamped to the valid range. Unset or invalid values result in
+/// [`DEFAULT_H2_WINDOW_SIZE`].
+fn parse_h2_window_size(value: Option<&str>) -> u32 {
+    match value.map(|value| value.trim().parse::<u64>()) {
+        Some(Ok(size)) => size.clamp(MIN_H2_WINDOW_SIZE as u64, MAX_H2_WINDOW_SIZE as u64) as u32,
+        Some(Err(err)) => {
+            log::warn!("ignoring invalid HTTP/2 window size - {err}");
+            DEFAULT_H2_WINDOW_SIZE
+        }
+        None => DEFAULT_H2_WINDOW_SIZE,
+    }
+}
+
+/// Returns the HTTP/2 initial stream and connection window sizes for protocol upgrades.
+///
+/// They can be overridden with the `PROXMOX_BACKUP_H2_STREAM_WINDOW_SIZE` and
+/// `PROXMOX_BACKUP_H2_CONNECTION_WINDOW_SIZE` environment variables (in bytes). Larger windows
+/// help on high latency links, smaller ones limit the memory used per connection.
+pub fn h2_window_sizes() -> (u32, u32) {
+    let stream = std::env::var("PROXMOX_BACKUP_H2_STREAM_WINDOW_SIZE").ok();
+    let connection = std::env::var("PROXMOX_BACKUP_H2_CONNECTION_WINDOW_SIZE").ok();
+    (
+        parse_h2_window_size(stream.as_deref()),
+        parse_h2_window_size(connection.as_deref()),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_h2_window_size() {
+        assert_eq!(parse_h2_window_size(None), DEFAULT_H2_WINDOW_SIZE);
+        assert_eq!(parse_h2_window_size(Some("1048576")), 1024 * 1024);
+        assert_eq!(parse_h2_window_size(Some(" 1048576\n")), 1024 * 1024);
+
+        // clamped to the valid range
+        assert_eq!(parse_h2_window_size(Some("0")), MIN_H2_WINDOW_SIZE);
+        assert_eq!(parse_h2_window_size(Some("4294967296")), (1 << 31) - 2);
+        assert_eq!(parse_h2_window_size(Some("2147483647")), (1 << 31) - 2);
+
+        assert_eq!(parse_h2_window_size(Some("-1")), DEFAULT_H2_WINDOW_SIZE);
+        assert_eq!(parse_h2_window_size(Some("32M")), DEFAULT_H2_WINDOW_SIZE);
+    }
+}
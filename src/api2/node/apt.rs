@@ -15,8 +15,8 @@ use proxmox_apt::repositories::{
 use proxmox_http::ProxyConfig;
 
 use pbs_api_types::{
-    APTUpdateInfo, NODE_SCHEMA, PRIV_SYS_AUDIT, PRIV_SYS_MODIFY, PROXMOX_CONFIG_DIGEST_SCHEMA,
-    UPID_SCHEMA,
+    APTUpdateInfo, WorkerType, NODE_SCHEMA, PRIV_SYS_AUDIT, PRIV_SYS_MODIFY,
+    PROXMOX_CONFIG_DIGEST_SCHEMA, UPID_SCHEMA,
 };
 
 use crate::config::node;
@@ -160,38 +160,44 @@ pub fn apt_update_database(
     let auth_id = rpcenv.get_auth_id().unwrap();
     let to_stdout = rpcenv.env_type() == RpcEnvironmentType::CLI;
 
-    let upid_str = WorkerTask::new_thread("aptupdate", None, auth_id, to_stdout, move |worker| {
-        do_apt_update(&worker, quiet)?;
-
-        let mut cache = apt::update_cache()?;
-
-        if notify {
-            let mut notified = match cache.notified {
-                Some(notified) => notified,
-                None => std::collections::HashMap::new(),
-            };
-            let mut to_notify: Vec<&APTUpdateInfo> = Vec::new();
-
-            for pkg in &cache.package_status {
-                match notified.insert(pkg.package.to_owned(), pkg.version.to_owned()) {
-                    Some(notified_version) => {
-                        if notified_version != pkg.version {
-                            to_notify.push(pkg);
+    let upid_str = WorkerTask::new_thread(
+        WorkerType::AptUpdate.as_str(),
+        None,
+        auth_id,
+        to_stdout,
+        move |worker| {
+            do_apt_update(&worker, quiet)?;
+
+            let mut cache = apt::update_cache()?;
+
+            if notify {
+                let mut notified = match cache.notified {
+                    Some(notified) => notified,
+                    None => std::collections::HashMap::new(),
+                };
+                let mut to_notify: Vec<&APTUpdateInfo> = Vec::new();
+
+                for pkg in &cache.package_status {
+                    match notified.insert(pkg.package.to_owned(), pkg.version.to_owned()) {
+                        Some(notified_version) => {
+                            if notified_version != pkg.version {
+                                to_notify.push(pkg);
+                            }
                         }
+                        None => to_notify.push(pkg),
                     }
-                    None => to_notify.push(pkg),
                 }
+                if !to_notify.is_empty() {
+                    to_notify.sort_unstable_by_key(|k| &k.package);
+                    crate::server::send_updates_available(&to_notify)?;
+                }
+                cache.notified = Some(notified);
+                apt::write_pkg_cache(&cache)?;
             }
-            if !to_notify.is_empty() {
-                to_notify.sort_unstable_by_key(|k| &k.package);
-                crate::server::send_updates_available(&to_notify)?;
-            }
-            cache.notified = Some(notified);
-            apt::write_pkg_cache(&cache)?;
-        }
 
-        Ok(())
-    })?;
+            Ok(())
+        },
+    )?;
 
     Ok(upid_str)
 }
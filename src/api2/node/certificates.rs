@@ -12,7 +12,7 @@ use proxmox_router::{Permission, Router, RpcEnvironment};
 use proxmox_schema::api;
 use proxmox_sys::{task_log, task_warn};
 
-use pbs_api_types::{NODE_SCHEMA, PRIV_SYS_MODIFY};
+use pbs_api_types::{WorkerType, NODE_SCHEMA, PRIV_SYS_MODIFY};
 use pbs_buildcfg::configdir;
 use pbs_tools::cert;
 
@@ -489,7 +489,7 @@ async fn request_validation(
 )]
 /// Order a new ACME certificate.
 pub fn new_acme_cert(force: bool, rpcenv: &mut dyn RpcEnvironment) -> Result<String, Error> {
-    spawn_certificate_worker("acme-new-cert", force, rpcenv)
+    spawn_certificate_worker(WorkerType::AcmeNewCert.as_str(), force, rpcenv)
 }
 
 #[api(
@@ -516,7 +516,7 @@ pub fn renew_acme_cert(force: bool, rpcenv: &mut dyn RpcEnvironment) -> Result<S
         bail!("Certificate does not expire within the next 30 days and 'force' is not set.")
     }
 
-    spawn_certificate_worker("acme-renew-cert", force, rpcenv)
+    spawn_certificate_worker(WorkerType::AcmeRenewCert.as_str(), force, rpcenv)
 }
 
 /// Check whether the current certificate expires within the next 30 days.
@@ -578,7 +578,7 @@ pub fn revoke_acme_cert(rpcenv: &mut dyn RpcEnvironment) -> Result<String, Error
     let auth_id = rpcenv.get_auth_id().unwrap();
 
     WorkerTask::spawn(
-        "acme-revoke-cert",
+        WorkerType::AcmeRevokeCert.as_str(),
         None,
         auth_id,
         true,
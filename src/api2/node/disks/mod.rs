@@ -9,7 +9,7 @@ use proxmox_sortable_macro::sortable;
 use proxmox_sys::task_log;
 
 use pbs_api_types::{
-    BLOCKDEVICE_DISK_AND_PARTITION_NAME_SCHEMA, BLOCKDEVICE_NAME_SCHEMA, NODE_SCHEMA,
+    WorkerType, BLOCKDEVICE_DISK_AND_PARTITION_NAME_SCHEMA, BLOCKDEVICE_NAME_SCHEMA, NODE_SCHEMA,
     PRIV_SYS_AUDIT, PRIV_SYS_MODIFY, UPID_SCHEMA,
 };
 
@@ -160,7 +160,7 @@ pub fn initialize_disk(
     }
 
     let upid_str = WorkerTask::new_thread(
-        "diskinit",
+        WorkerType::DiskInit.as_str(),
         Some(disk.clone()),
         auth_id,
         to_stdout,
@@ -205,7 +205,7 @@ pub fn wipe_disk(disk: String, rpcenv: &mut dyn RpcEnvironment) -> Result<Value,
     let auth_id = rpcenv.get_auth_id().unwrap();
 
     let upid_str = WorkerTask::new_thread(
-        "wipedisk",
+        WorkerType::WipeDisk.as_str(),
         Some(disk.clone()),
         auth_id,
         to_stdout,
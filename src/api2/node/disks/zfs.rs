@@ -6,7 +6,7 @@ use proxmox_schema::api;
 use proxmox_sys::{task_error, task_log};
 
 use pbs_api_types::{
-    DataStoreConfig, ZfsCompressionType, ZfsRaidLevel, ZpoolListItem, DATASTORE_SCHEMA,
+    DataStoreConfig, WorkerType, ZfsCompressionType, ZfsRaidLevel, ZpoolListItem, DATASTORE_SCHEMA,
     DISK_ARRAY_SCHEMA, DISK_LIST_SCHEMA, NODE_SCHEMA, PRIV_SYS_AUDIT, PRIV_SYS_MODIFY, UPID_SCHEMA,
     ZFS_ASHIFT_SCHEMA, ZPOOL_NAME_SCHEMA,
 };
@@ -224,7 +224,7 @@ pub fn create_zpool(
     }
 
     let upid_str = WorkerTask::new_thread(
-        "zfscreate",
+        WorkerType::ZfsCreate.as_str(),
         Some(name.clone()),
         auth_id,
         to_stdout,
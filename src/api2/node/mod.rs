@@ -24,7 +24,7 @@ use proxmox_schema::*;
 use proxmox_sortable_macro::sortable;
 use proxmox_sys::fd::fd_change_cloexec;
 
-use pbs_api_types::{NODE_SCHEMA, PRIV_SYS_CONSOLE};
+use pbs_api_types::{WorkerType, NODE_SCHEMA, PRIV_SYS_CONSOLE};
 
 use crate::auth::{private_auth_keyring, public_auth_keyring};
 use crate::tools;
@@ -146,7 +146,7 @@ async fn termproxy(cmd: Option<String>, rpcenv: &mut dyn RpcEnvironment) -> Resu
 
     let username = userid.name().to_owned();
     let upid = WorkerTask::spawn(
-        "termproxy",
+        WorkerType::TermProxy.as_str(),
         None,
         auth_id.to_string(),
         false,
@@ -342,6 +342,10 @@ pub const SUBDIRS: SubdirMap = &[
         "vncwebsocket",
         &Router::new().upgrade(&API_METHOD_WEBSOCKET),
     ),
+    (
+        "worker-types",
+        &Router::new().get(&tasks::API_METHOD_LIST_WORKER_TYPES),
+    ),
 ];
 
 pub const ITEM_ROUTER: Router = Router::new()
@@ -8,7 +8,7 @@ use proxmox_schema::api;
 
 use pbs_api_types::{
     Authid, BondXmitHashPolicy, Interface, LinuxBondMode, NetworkConfigMethod,
-    NetworkInterfaceType, CIDR_V4_SCHEMA, CIDR_V6_SCHEMA, IP_V4_SCHEMA, IP_V6_SCHEMA,
+    NetworkInterfaceType, WorkerType, CIDR_V4_SCHEMA, CIDR_V6_SCHEMA, IP_V4_SCHEMA, IP_V6_SCHEMA,
     NETWORK_INTERFACE_ARRAY_SCHEMA, NETWORK_INTERFACE_LIST_SCHEMA, NETWORK_INTERFACE_NAME_SCHEMA,
     NODE_SCHEMA, PRIV_SYS_AUDIT, PRIV_SYS_MODIFY, PROXMOX_CONFIG_DIGEST_SCHEMA,
 };
@@ -857,7 +857,7 @@ pub async fn reload_network_config(rpcenv: &mut dyn RpcEnvironment) -> Result<St
     let auth_id: Authid = rpcenv.get_auth_id().unwrap().parse()?;
 
     let upid_str = WorkerTask::spawn(
-        "srvreload",
+        WorkerType::SrvReload.as_str(),
         Some(String::from("networking")),
         auth_id.to_string(),
         true,
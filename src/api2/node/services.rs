@@ -7,7 +7,9 @@ use proxmox_router::{list_subdirs_api_method, Permission, Router, RpcEnvironment
 use proxmox_schema::api;
 use proxmox_sortable_macro::sortable;
 
-use pbs_api_types::{Authid, NODE_SCHEMA, PRIV_SYS_AUDIT, PRIV_SYS_MODIFY, SERVICE_ID_SCHEMA};
+use pbs_api_types::{
+    Authid, WorkerType, NODE_SCHEMA, PRIV_SYS_AUDIT, PRIV_SYS_MODIFY, SERVICE_ID_SCHEMA,
+};
 
 use proxmox_rest_server::WorkerTask;
 
@@ -192,17 +194,18 @@ fn get_service_state(service: String, _param: Value) -> Result<Value, Error> {
 }
 
 fn run_service_command(service: &str, cmd: &str, auth_id: Authid) -> Result<Value, Error> {
-    let workerid = format!("srv{}", &cmd);
-
-    let cmd = match cmd {
-        "start" | "stop" | "restart" => cmd.to_string(),
-        "reload" => "try-reload-or-restart".to_string(), // some services do not implement reload
+    let (worker_type, cmd) = match cmd {
+        "start" => (WorkerType::SrvStart, cmd.to_string()),
+        "stop" => (WorkerType::SrvStop, cmd.to_string()),
+        "restart" => (WorkerType::SrvRestart, cmd.to_string()),
+        // some services do not implement reload
+        "reload" => (WorkerType::SrvReload, "try-reload-or-restart".to_string()),
         _ => bail!("unknown service command '{}'", cmd),
     };
     let service = service.to_string();
 
     let upid = WorkerTask::new_thread(
-        &workerid,
+        worker_type.as_str(),
         Some(service.clone()),
         auth_id.to_string(),
         false,
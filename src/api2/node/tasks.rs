@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 
 use anyhow::{bail, Error};
 use futures::FutureExt;
@@ -17,9 +17,10 @@ use proxmox_schema::{api, BooleanSchema, IntegerSchema, ObjectSchema, Schema};
 use proxmox_sortable_macro::sortable;
 
 use pbs_api_types::{
-    Authid, TaskListItem, TaskStateType, Tokenname, Userid, DATASTORE_SCHEMA, NODE_SCHEMA,
-    PRIV_DATASTORE_MODIFY, PRIV_DATASTORE_VERIFY, PRIV_SYS_AUDIT, PRIV_SYS_MODIFY,
-    SYNC_JOB_WORKER_ID_REGEX, UPID, UPID_SCHEMA, VERIFICATION_JOB_WORKER_ID_REGEX,
+    Authid, TaskListItem, TaskListSortOrder, TaskStateType, Tokenname, Userid, WorkerType,
+    WorkerTypeInfo, DATASTORE_SCHEMA, NODE_SCHEMA, PRIV_DATASTORE_MODIFY, PRIV_DATASTORE_VERIFY,
+    PRIV_SYS_AUDIT, PRIV_SYS_MODIFY, SYNC_JOB_WORKER_ID_REGEX, UPID, UPID_SCHEMA,
+    VERIFICATION_JOB_WORKER_ID_REGEX,
 };
 
 use crate::api2::pull::check_pull_privs;
@@ -41,6 +42,13 @@ pub const LIMIT_PARAM_SCHEMA: Schema = IntegerSchema::new(
 .default(50)
 .schema();
 
+pub const TAIL_PARAM_SCHEMA: Schema = IntegerSchema::new(
+    "Only return the last N lines of the tasklog. \
+        This parameter can't be used in conjunction with 'start' and 'limit'",
+)
+.minimum(1)
+.schema();
+
 pub const DOWNLOAD_PARAM_SCHEMA: Schema = BooleanSchema::new(
     "Whether the tasklog file should be downloaded. \
         This parameter can't be used in conjunction with other parameters",
@@ -177,11 +185,31 @@ pub fn tasktype(state: &TaskState) -> TaskStateType {
     }
 }
 
+/// Returns the number of warnings logged by a finished task, `0` unless it ended with warnings.
+fn task_warnings(state: &TaskState) -> u64 {
+    match state {
+        TaskState::Warning { count, .. } => *count,
+        _ => 0,
+    }
+}
+
+/// Returns the run time of a task, using `now` as end for still running tasks.
+fn task_duration(starttime: i64, endtime: Option<i64>, now: i64) -> i64 {
+    (endtime.unwrap_or(now) - starttime).max(0)
+}
+
 fn into_task_list_item(info: proxmox_rest_server::TaskListInfo) -> pbs_api_types::TaskListItem {
-    let (endtime, status) = info.state.map_or_else(
-        || (None, None),
-        |a| (Some(a.endtime()), Some(a.to_string())),
+    let (endtime, status, warnings) = info.state.map_or_else(
+        || (None, None, None),
+        |a| {
+            (
+                Some(a.endtime()),
+                Some(a.to_string()),
+                Some(task_warnings(&a)),
+            )
+        },
     );
+    let duration = task_duration(info.upid.starttime, endtime, proxmox_time::epoch_i64());
 
     pbs_api_types::TaskListItem {
         upid: info.upid_str,
@@ -194,6 +222,8 @@ fn into_task_list_item(info: proxmox_rest_server::TaskListInfo) -> pbs_api_types
         user: info.upid.auth_id,
         endtime,
         status,
+        warnings,
+        duration: Some(duration),
     }
 }
 
@@ -254,6 +284,11 @@ fn into_task_list_item(info: proxmox_rest_server::TaskListInfo) -> pbs_api_types
                 optional: true,
                 description: "'OK', 'Error: <msg>', or 'unkwown'.",
             },
+            warnings: {
+                type: u64,
+                optional: true,
+                description: "Number of warnings of a stopped task, 0 unless it ended with warnings.",
+            },
         },
     },
     access: {
@@ -291,6 +326,7 @@ async fn get_task_status(param: Value, rpcenv: &mut dyn RpcEnvironment) -> Resul
         let exitstatus = upid_read_status(&upid).unwrap_or(TaskState::Unknown { endtime: 0 });
         result["status"] = Value::from("stopped");
         result["exitstatus"] = Value::from(exitstatus.to_string());
+        result["warnings"] = Value::from(task_warnings(&exitstatus));
     };
 
     Ok(result)
@@ -310,6 +346,7 @@ pub const API_METHOD_READ_TASK_LOG: ApiMethod = ApiMethod::new(
             ("upid", false, &UPID_SCHEMA),
             ("start", true, &START_PARAM_SCHEMA),
             ("limit", true, &LIMIT_PARAM_SCHEMA),
+            ("tail", true, &TAIL_PARAM_SCHEMA),
             ("download", true, &DOWNLOAD_PARAM_SCHEMA),
             ("test-status", true, &TEST_STATUS_PARAM_SCHEMA)
         ]),
@@ -337,6 +374,7 @@ fn read_task_log(
         if download {
             if !param["start"].is_null()
                 || !param["limit"].is_null()
+                || !param["tail"].is_null()
                 || !param["test-status"].is_null()
             {
                 bail!("Parameter 'download' cannot be used with other parameters");
@@ -361,12 +399,43 @@ fn read_task_log(
         let mut limit = param["limit"].as_u64().unwrap_or(50);
         let test_status = param["test-status"].as_bool().unwrap_or(false);
 
-        let file = File::open(path)?;
+        let mut file = File::open(path)?;
 
         let mut count: u64 = 0;
         let mut lines: Vec<Value> = vec![];
         let read_until_end = limit == 0;
 
+        if let Some(tail) = param["tail"].as_u64() {
+            if !param["start"].is_null() || !param["limit"].is_null() {
+                bail!("Parameter 'tail' cannot be used with 'start' or 'limit'");
+            }
+
+            // the real line numbers are unknown, as we never read the start of the file
+            let (tail_lines, truncated) = read_tail_lines(&mut file, tail)?;
+            for line in tail_lines {
+                count += 1;
+                lines.push(json!({ "n": count, "t": line }));
+            }
+
+            let mut json = json!({
+                "data": lines,
+                "total": count,
+                "truncated": truncated,
+                "success": 1,
+            });
+
+            if test_status {
+                let active = proxmox_rest_server::worker_is_active(&upid).await?;
+                json["active"] = Value::from(active);
+            }
+
+            return Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json.to_string()))
+                .unwrap());
+        }
+
         for line in BufReader::new(file).lines() {
             match line {
                 Ok(line) => {
@@ -410,6 +479,56 @@ fn read_task_log(
     .boxed()
 }
 
+/// Read the last `count` lines by scanning the file backwards from its end.
+///
+/// Returns the lines in file order and whether the file contains more lines before them.
+fn read_tail_lines<R: Read + Seek>(
+    reader: &mut R,
+    count: u64,
+) -> Result<(Vec<String>, bool), Error> {
+    const BLOCK_SIZE: u64 = 64 * 1024;
+
+    let mut pos = reader.seek(SeekFrom::End(0))?;
+    let mut data = Vec::new();
+    let mut newlines = 0;
+
+    while pos > 0 {
+        let len = pos.min(BLOCK_SIZE);
+        pos -= len;
+        reader.seek(SeekFrom::Start(pos))?;
+        let mut block = vec![0u8; len as usize];
+        reader.read_exact(&mut block)?;
+        newlines += block.iter().filter(|b| **b == b'\n').count() as u64;
+        block.extend_from_slice(&data);
+        data = block;
+
+        // the final newline only terminates the last line, all lines before it need their own
+        let trailing_newline = data.last() == Some(&b'\n');
+        if newlines >= count + trailing_newline as u64 {
+            break;
+        }
+    }
+
+    let mut lines: Vec<&[u8]> = data.split(|b| *b == b'\n').collect();
+    if data.last() == Some(&b'\n') {
+        lines.pop();
+    }
+    if pos > 0 {
+        // the first line is incomplete
+        lines.remove(0);
+    }
+
+    let skip = lines.len().saturating_sub(count as usize);
+    let truncated = pos > 0 || skip > 0;
+
+    let lines = lines[skip..]
+        .iter()
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect();
+
+    Ok((lines, truncated))
+}
+
 #[api(
     protected: true,
     input: {
@@ -507,6 +626,10 @@ fn stop_task(param: Value, rpcenv: &mut dyn RpcEnvironment) -> Result<Value, Err
                     type: TaskStateType,
                 },
             },
+            sortby: {
+                type: TaskListSortOrder,
+                optional: true,
+            },
         },
     },
     returns: pbs_api_types::NODE_TASKS_LIST_TASKS_RETURN_TYPE,
@@ -527,6 +650,7 @@ pub fn list_tasks(
     until: Option<i64>,
     typefilter: Option<String>,
     statusfilter: Option<Vec<TaskStateType>>,
+    sortby: Option<TaskListSortOrder>,
     param: Value,
     rpcenv: &mut dyn RpcEnvironment,
 ) -> Result<Vec<TaskListItem>, Error> {
@@ -545,6 +669,9 @@ pub fn list_tasks(
         usize::MAX
     };
 
+    // sorting by run time needs all matching tasks, so paginate afterwards
+    let sort_by_runtime = sortby.unwrap_or_default() == TaskListSortOrder::Runtime;
+
     let mut skipped = 0;
     let mut result: Vec<TaskListItem> = Vec::new();
 
@@ -606,6 +733,11 @@ pub fn list_tasks(
             _ => {}
         }
 
+        if sort_by_runtime {
+            result.push(into_task_list_item(info));
+            continue;
+        }
+
         if skipped < start as usize {
             skipped += 1;
             continue;
@@ -618,6 +750,19 @@ pub fn list_tasks(
         }
     }
 
+    if sort_by_runtime {
+        // stable sort, so tasks with equal run time stay sorted by start time
+        result.sort_by_key(|item| std::cmp::Reverse(item.duration));
+        let total = result.len();
+        let result: Vec<TaskListItem> = result
+            .into_iter()
+            .skip(start as usize)
+            .take(limit)
+            .collect();
+        rpcenv["total"] = Value::from(total);
+        return Ok(result);
+    }
+
     let mut count = result.len() + start as usize;
     if !result.is_empty() && result.len() >= limit {
         // we have a 'virtual' entry as long as we have any new
@@ -629,6 +774,36 @@ pub fn list_tasks(
     Ok(result)
 }
 
+#[api(
+    input: {
+        properties: {
+            node: {
+                schema: NODE_SCHEMA
+            },
+        },
+    },
+    returns: {
+        description: "List of known worker task types.",
+        type: Array,
+        items: {
+            type: WorkerTypeInfo,
+        },
+    },
+    access: {
+        permission: &Permission::Anybody,
+    },
+)]
+/// List the worker task types the server can produce, with a human readable label.
+pub fn list_worker_types() -> Result<Vec<WorkerTypeInfo>, Error> {
+    Ok(WorkerType::ALL
+        .iter()
+        .map(|worker_type| WorkerTypeInfo {
+            worker_type: worker_type.as_str().to_string(),
+            label: worker_type.label().to_string(),
+        })
+        .collect())
+}
+
 #[sortable]
 const UPID_API_SUBDIRS: SubdirMap = &sorted!([
     ("log", &Router::new().get(&API_METHOD_READ_TASK_LOG)),
@@ -643,3 +818,83 @@ pub const UPID_API_ROUTER: Router = Router::new()
 pub const ROUTER: Router = Router::new()
     .get(&API_METHOD_LIST_TASKS)
     .match_all("upid", &UPID_API_ROUTER);
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use proxmox_rest_server::TaskState;
+
+    use pbs_api_types::{is_known_worker_type, worker_type_label, WorkerType};
+
+    use super::{read_tail_lines, task_duration, task_warnings};
+
+    #[test]
+    fn test_task_warnings() {
+        let endtime = 1000;
+        assert_eq!(task_warnings(&TaskState::OK { endtime }), 0);
+        assert_eq!(task_warnings(&TaskState::Warning { count: 3, endtime }), 3);
+        let message = String::from("failed");
+        assert_eq!(task_warnings(&TaskState::Error { message, endtime }), 0);
+        assert_eq!(task_warnings(&TaskState::Unknown { endtime }), 0);
+    }
+
+    #[test]
+    fn test_task_duration() {
+        // finished task
+        assert_eq!(task_duration(1000, Some(1042), 5000), 42);
+        // running task, counted up to now
+        assert_eq!(task_duration(1000, None, 1100), 100);
+        // clock skew must not produce negative durations
+        assert_eq!(task_duration(1000, None, 900), 0);
+    }
+
+    #[test]
+    fn test_read_tail_lines() {
+        let mut log = String::new();
+        for i in 1..=100_000 {
+            log.push_str(&format!("line {i}\n"));
+        }
+
+        let (lines, truncated) = read_tail_lines(&mut Cursor::new(log.as_bytes()), 50).unwrap();
+        assert!(truncated);
+        assert_eq!(lines.len(), 50);
+        assert_eq!(lines[0], "line 99951");
+        assert_eq!(lines[49], "line 100000");
+
+        // last line without newline
+        let data = log.trim_end().as_bytes();
+        let (lines, truncated) = read_tail_lines(&mut Cursor::new(data), 2).unwrap();
+        assert!(truncated);
+        assert_eq!(lines, vec!["line 99999", "line 100000"]);
+
+        let (lines, truncated) = read_tail_lines(&mut Cursor::new(&b"a\nb\n"[..]), 10).unwrap();
+        assert!(!truncated);
+        assert_eq!(lines, vec!["a", "b"]);
+
+        let (lines, truncated) = read_tail_lines(&mut Cursor::new(&b"a\nb\n"[..]), 2).unwrap();
+        assert!(!truncated);
+        assert_eq!(lines, vec!["a", "b"]);
+
+        let (lines, truncated) = read_tail_lines(&mut Cursor::new(&b""[..]), 2).unwrap();
+        assert!(!truncated);
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn test_worker_types() {
+        let mut names = std::collections::HashSet::new();
+        for worker_type in WorkerType::ALL {
+            let name = worker_type.as_str();
+            assert!(names.insert(name), "duplicate worker type {name}");
+            assert_eq!(name.parse::<WorkerType>().unwrap(), *worker_type);
+            assert_eq!(worker_type_label(name), Some(worker_type.label()));
+        }
+
+        assert!(WorkerType::ALL
+            .windows(2)
+            .all(|pair| pair[0].as_str() < pair[1].as_str()));
+        assert_eq!(worker_type_label("backup"), Some("Backup"));
+        assert!(!is_known_worker_type("no-such-worker"));
+    }
+}
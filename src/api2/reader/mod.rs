@@ -2,12 +2,11 @@
 
 use anyhow::{bail, format_err, Error};
 use futures::*;
-use hex::FromHex;
 use hyper::header::{self, HeaderValue, CONNECTION, UPGRADE};
 use hyper::http::request::Parts;
 use hyper::{Body, Request, Response, StatusCode};
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{json, Value};
 
 use proxmox_router::{
     http_err, list_subdirs_api_method, ApiHandler, ApiMethod, ApiResponseFuture, Permission,
@@ -17,12 +16,12 @@ use proxmox_schema::{BooleanSchema, ObjectSchema};
 use proxmox_sortable_macro::sortable;
 
 use pbs_api_types::{
-    Authid, Operation, BACKUP_ARCHIVE_NAME_SCHEMA, BACKUP_ID_SCHEMA, BACKUP_NAMESPACE_SCHEMA,
-    BACKUP_TIME_SCHEMA, BACKUP_TYPE_SCHEMA, CHUNK_DIGEST_SCHEMA, DATASTORE_SCHEMA,
-    PRIV_DATASTORE_BACKUP, PRIV_DATASTORE_READ,
+    Authid, Operation, WorkerType, BACKUP_ARCHIVE_NAME_SCHEMA, BACKUP_ID_SCHEMA,
+    BACKUP_NAMESPACE_SCHEMA, BACKUP_TIME_SCHEMA, BACKUP_TYPE_SCHEMA, CHUNK_DIGEST_SCHEMA,
+    DATASTORE_SCHEMA, PRIV_DATASTORE_BACKUP, PRIV_DATASTORE_READ,
 };
 use pbs_config::CachedUserInfo;
-use pbs_datastore::index::IndexFile;
+use pbs_datastore::index::{parse_digest, IndexFile};
 use pbs_datastore::manifest::{archive_type, ArchiveType};
 use pbs_datastore::{DataStore, PROXMOX_BACKUP_READER_PROTOCOL_ID_V1};
 use pbs_tools::json::required_string_param;
@@ -149,7 +148,7 @@ fn upgrade_to_backup_reader_protocol(
         );
 
         WorkerTask::spawn(
-            "reader",
+            WorkerType::Reader.as_str(),
             Some(worker_id),
             auth_id.to_string(),
             true,
@@ -185,10 +184,10 @@ fn upgrade_to_backup_reader_protocol(
 
                     let mut http = hyper::server::conn::Http::new();
                     http.http2_only(true);
-                    // increase window size: todo - find optiomal size
-                    let window_size = 32 * 1024 * 1024; // max = (1 << 31) - 2
-                    http.http2_initial_stream_window_size(window_size);
-                    http.http2_initial_connection_window_size(window_size);
+                    let (stream_window_size, connection_window_size) =
+                        crate::api2::helpers::h2_window_sizes();
+                    http.http2_initial_stream_window_size(stream_window_size);
+                    http.http2_initial_connection_window_size(connection_window_size);
                     http.http2_max_frame_size(4 * 1024 * 1024);
 
                     http.serve_connection(conn, service)
@@ -227,6 +226,10 @@ const READER_API_SUBDIRS: SubdirMap = &[
         "download",
         &Router::new().download(&API_METHOD_DOWNLOAD_FILE),
     ),
+    (
+        "index_digests",
+        &Router::new().get(&API_METHOD_LIST_INDEX_DIGESTS),
+    ),
     ("speedtest", &Router::new().download(&API_METHOD_SPEEDTEST)),
 ];
 
@@ -290,6 +293,57 @@ fn download_file(
     .boxed()
 }
 
+#[sortable]
+pub const API_METHOD_LIST_INDEX_DIGESTS: ApiMethod = ApiMethod::new(
+    &ApiHandler::AsyncHttp(&list_index_digests),
+    &ObjectSchema::new(
+        "List the chunk digests and sizes of an index file, in index order.",
+        &sorted!([("file-name", false, &BACKUP_ARCHIVE_NAME_SCHEMA),]),
+    ),
+);
+
+fn list_index_digests(
+    _parts: Parts,
+    _req_body: Body,
+    param: Value,
+    _info: &ApiMethod,
+    rpcenv: Box<dyn RpcEnvironment>,
+) -> ApiResponseFuture {
+    async move {
+        let env: &ReaderEnvironment = rpcenv.as_ref();
+
+        let file_name = required_string_param(&param, "file-name")?.to_owned();
+
+        let mut path = env.backup_dir.relative_path();
+        path.push(&file_name);
+
+        env.log(format!("list digests of {:?}", path));
+
+        let index: Box<dyn IndexFile + Send> = match archive_type(&file_name)? {
+            ArchiveType::FixedIndex => Box::new(env.datastore.open_fixed_reader(&path)?),
+            ArchiveType::DynamicIndex => Box::new(env.datastore.open_dynamic_reader(&path)?),
+            ArchiveType::Blob => bail!("'{}' is not an index file", file_name),
+        };
+
+        Ok(env.format_response(Ok(index_digest_list(&*index))))
+    }
+    .boxed()
+}
+
+/// Returns the digests (hex) and sizes of all chunks referenced by `index`, in index order.
+fn index_digest_list(index: &dyn IndexFile) -> Value {
+    let list: Vec<Value> = (0..index.index_count())
+        .filter_map(|pos| index.chunk_info(pos))
+        .map(|info| {
+            json!({
+                "digest": hex::encode(info.digest),
+                "size": info.size(),
+            })
+        })
+        .collect();
+    Value::Array(list)
+}
+
 #[sortable]
 pub const API_METHOD_DOWNLOAD_CHUNK: ApiMethod = ApiMethod::new(
     &ApiHandler::AsyncHttp(&download_chunk),
@@ -310,7 +364,7 @@ fn download_chunk(
         let env: &ReaderEnvironment = rpcenv.as_ref();
 
         let digest_str = required_string_param(&param, "digest")?;
-        let digest = <[u8; 32]>::from_hex(digest_str)?;
+        let digest = parse_digest(digest_str)?;
 
         if !env.check_chunk_access(digest) {
             env.log(format!(
@@ -359,7 +413,7 @@ fn download_chunk_old(
     let env2 = env.clone();
 
     let digest_str = required_string_param(&param, "digest")?;
-    let digest = <[u8; 32]>::from_hex(digest_str)?;
+    let digest = parse_digest(digest_str)?;
 
     let (path, _) = env.datastore.chunk_path(&digest);
 
@@ -411,3 +465,67 @@ fn speedtest(
 
     future::ok(response).boxed()
 }
+
+#[cfg(test)]
+mod test {
+    use pbs_datastore::index::ChunkReadInfo;
+
+    use super::*;
+
+    struct TestIndex(Vec<ChunkReadInfo>);
+
+    impl IndexFile for TestIndex {
+        fn index_count(&self) -> usize {
+            self.0.len()
+        }
+        fn index_digest(&self, pos: usize) -> Option<&[u8; 32]> {
+            self.0.get(pos).map(|info| &info.digest)
+        }
+        fn index_bytes(&self) -> u64 {
+            self.0.last().map(|info| info.range.end).unwrap_or(0)
+        }
+        fn chunk_info(&self, pos: usize) -> Option<ChunkReadInfo> {
+            self.0.get(pos).cloned()
+        }
+        fn index_ctime(&self) -> i64 {
+            0
+        }
+        fn index_size(&self) -> usize {
+            0
+        }
+        fn chunk_from_offset(&self, _offset: u64) -> Option<(usize, u64)> {
+            None
+        }
+        fn compute_csum(&self) -> ([u8; 32], u64) {
+            ([0u8; 32], self.index_bytes())
+        }
+    }
+
+    #[test]
+    fn test_index_digest_list() {
+        let index = TestIndex(vec![
+            ChunkReadInfo {
+                range: 0..4096,
+                digest: [1u8; 32],
+            },
+            ChunkReadInfo {
+                range: 4096..6000,
+                digest: [2u8; 32],
+            },
+            ChunkReadInfo {
+                range: 6000..10096,
+                digest: [1u8; 32],
+            },
+        ]);
+
+        let list = index_digest_list(&index);
+        let list = list.as_array().unwrap();
+        assert_eq!(list.len(), index.index_count());
+
+        for (pos, entry) in list.iter().enumerate() {
+            let info = index.chunk_info(pos).unwrap();
+            assert_eq!(entry["digest"], hex::encode(info.digest));
+            assert_eq!(entry["size"], info.size());
+        }
+    }
+}
@@ -10,7 +10,7 @@ use proxmox_sys::{task_log, task_warn, WorkerTaskContext};
 
 use pbs_api_types::{
     print_ns_and_snapshot, print_store_and_ns, Authid, MediaPoolConfig, Operation,
-    TapeBackupJobConfig, TapeBackupJobSetup, TapeBackupJobStatus, JOB_ID_SCHEMA,
+    TapeBackupJobConfig, TapeBackupJobSetup, TapeBackupJobStatus, WorkerType, JOB_ID_SCHEMA,
     PRIV_DATASTORE_READ, PRIV_TAPE_AUDIT, PRIV_TAPE_WRITE, UPID_SCHEMA,
 };
 
@@ -261,7 +261,7 @@ pub fn run_tape_backup_job(id: String, rpcenv: &mut dyn RpcEnvironment) -> Resul
         &backup_job.setup.drive,
     )?;
 
-    let job = Job::new("tape-backup-job", &id)?;
+    let job = Job::new(WorkerType::TapeBackupJob.as_str(), &id)?;
 
     let to_stdout = rpcenv.env_type() == RpcEnvironmentType::CLI;
 
@@ -320,7 +320,7 @@ pub fn backup(
     let job_id = format!("{}:{}:{}", setup.store, setup.pool, setup.drive);
 
     let upid_str = WorkerTask::new_thread(
-        "tape-backup",
+        WorkerType::TapeBackup.as_str(),
         Some(job_id),
         auth_id.to_string(),
         to_stdout,
@@ -17,8 +17,8 @@ use proxmox_uuid::Uuid;
 
 use pbs_api_types::{
     Authid, DriveListEntry, LabelUuidMap, Lp17VolumeStatistics, LtoDriveAndMediaStatus,
-    LtoTapeDrive, MamAttribute, MediaIdFlat, TapeDensity, CHANGER_NAME_SCHEMA, DRIVE_NAME_SCHEMA,
-    MEDIA_LABEL_SCHEMA, MEDIA_POOL_NAME_SCHEMA, UPID_SCHEMA,
+    LtoTapeDrive, MamAttribute, MediaIdFlat, TapeDensity, WorkerType, CHANGER_NAME_SCHEMA,
+    DRIVE_NAME_SCHEMA, MEDIA_LABEL_SCHEMA, MEDIA_POOL_NAME_SCHEMA, UPID_SCHEMA,
 };
 
 use pbs_api_types::{PRIV_TAPE_AUDIT, PRIV_TAPE_READ, PRIV_TAPE_WRITE};
@@ -129,7 +129,7 @@ pub fn load_media(
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "load-media",
+        WorkerType::LoadMedia.as_str(),
         Some(job_id),
         move |worker, config| {
             task_log!(
@@ -248,7 +248,7 @@ pub fn unload(
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "unload-media",
+        WorkerType::UnloadMedia.as_str(),
         Some(drive.clone()),
         move |worker, config| {
             task_log!(worker, "unloading media from drive '{}'", drive);
@@ -297,7 +297,7 @@ pub fn format_media(
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "format-media",
+        WorkerType::FormatMedia.as_str(),
         Some(drive.clone()),
         move |worker, config| {
             if let Some(ref label) = label_text {
@@ -410,7 +410,7 @@ pub fn rewind(drive: String, rpcenv: &mut dyn RpcEnvironment) -> Result<Value, E
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "rewind-media",
+        WorkerType::RewindMedia.as_str(),
         Some(drive.clone()),
         move |_worker, config| {
             let mut drive = open_drive(&config, &drive)?;
@@ -442,7 +442,7 @@ pub fn eject_media(drive: String, rpcenv: &mut dyn RpcEnvironment) -> Result<Val
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "eject-media",
+        WorkerType::EjectMedia.as_str(),
         Some(drive.clone()),
         move |_worker, config| {
             if let Some((mut changer, _)) = media_changer(&config, &drive)? {
@@ -502,7 +502,7 @@ pub fn label_media(
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "label-media",
+        WorkerType::LabelMedia.as_str(),
         Some(drive.clone()),
         move |worker, config| {
             let mut drive = open_drive(&config, &drive)?;
@@ -747,7 +747,7 @@ pub fn clean_drive(drive: String, rpcenv: &mut dyn RpcEnvironment) -> Result<Val
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "clean-drive",
+        WorkerType::CleanDrive.as_str(),
         Some(drive.clone()),
         move |worker, config| {
             let (mut changer, _changer_name) = required_media_changer(&config, &drive)?;
@@ -908,7 +908,7 @@ pub fn update_inventory(
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "inventory-update",
+        WorkerType::InventoryUpdate.as_str(),
         Some(drive.clone()),
         move |worker, config| {
             let (mut changer, changer_name) = required_media_changer(&config, &drive)?;
@@ -1064,7 +1064,7 @@ pub fn barcode_label_media(
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "barcode-label-media",
+        WorkerType::BarcodeLabelMedia.as_str(),
         Some(drive.clone()),
         move |worker, config| barcode_label_media_worker(worker, drive, &config, pool),
     )?;
@@ -1309,7 +1309,7 @@ pub fn catalog_media(
     let upid_str = run_drive_worker(
         rpcenv,
         drive.clone(),
-        "catalog-media",
+        WorkerType::CatalogMedia.as_str(),
         Some(drive.clone()),
         move |worker, config| {
             let mut drive = open_drive(&config, &drive)?;
@@ -1,27 +1,164 @@
 use nix::dir::Dir;
 use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, format_err, Error};
+use serde::Serialize;
 
 use proxmox_sys::{task_log, WorkerTaskContext};
 
 use pbs_api_types::{
     print_ns_and_snapshot, print_store_and_ns, Authid, BackupNamespace, BackupType, CryptMode,
-    SnapshotVerifyState, VerifyState, PRIV_DATASTORE_BACKUP, PRIV_DATASTORE_VERIFY, UPID,
+    SnapshotVerifyState, VerifyMode, VerifyState, PRIV_DATASTORE_BACKUP, PRIV_DATASTORE_VERIFY,
+    UPID,
 };
 use pbs_datastore::backup_info::{BackupDir, BackupGroup, BackupInfo};
 use pbs_datastore::index::IndexFile;
 use pbs_datastore::manifest::{archive_type, ArchiveType, BackupManifest, FileInfo};
 use pbs_datastore::{DataBlob, DataStore, StoreProgress};
-use proxmox_sys::fs::lock_dir_noblock_shared;
+use proxmox_sys::fs::{
+    file_get_optional_contents, lock_dir_noblock_shared, replace_file, CreateOptions,
+};
 
 use crate::tools::parallel_handler::ParallelHandler;
 
 use crate::backup::hierarchy::ListAccessibleBackupGroups;
 
+/// Upper limit for the default number of chunk decoder threads.
+const DEFAULT_MAX_WORKER_THREADS: usize = 16;
+
+// openssl::sha::sha256(b"Proxmox Backup verify checkpoint v1.0")[0..8]
+const VERIFY_CHECKPOINT_MAGIC_1_0: [u8; 8] = [8, 218, 11, 227, 74, 134, 95, 246];
+
+/// Minimal time between two periodic checkpoint writes.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Verified and corrupt chunks of a previous, unfinished verification run.
+///
+/// The file consists of the magic, the start time of the run which created the checkpoint, the
+/// number of verified and corrupt chunks (all little endian) followed by the digests.
+struct VerifyCheckpoint {
+    time: i64,
+    verified: HashSet<[u8; 32]>,
+    corrupt: HashSet<[u8; 32]>,
+}
+
+impl VerifyCheckpoint {
+    /// Load a checkpoint, returns `None` if there is none or it is older than `max_age` seconds.
+    fn load(path: &Path, max_age: i64, now: i64) -> Result<Option<Self>, Error> {
+        let data = match file_get_optional_contents(path)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+
+        if data.len() < 32 || data[..8] != VERIFY_CHECKPOINT_MAGIC_1_0 {
+            bail!("invalid verify checkpoint {path:?}");
+        }
+        let read_u64 = |pos: usize| u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap());
+
+        let time = read_u64(8) as i64;
+        if now.saturating_sub(time) > max_age {
+            return Ok(None);
+        }
+
+        let verified_count = read_u64(16) as usize;
+        let corrupt_count = read_u64(24) as usize;
+        let digests = &data[32..];
+        let expected_size = verified_count
+            .checked_add(corrupt_count)
+            .and_then(|count| count.checked_mul(32));
+        if expected_size != Some(digests.len()) {
+            bail!("invalid verify checkpoint {path:?} - unexpected size");
+        }
+
+        let mut digests = digests
+            .chunks_exact(32)
+            .map(|digest| <[u8; 32]>::try_from(digest).unwrap());
+
+        Ok(Some(Self {
+            time,
+            verified: digests.by_ref().take(verified_count).collect(),
+            corrupt: digests.collect(),
+        }))
+    }
+
+    fn save(
+        path: &Path,
+        time: i64,
+        verified: &HashSet<[u8; 32]>,
+        corrupt: &HashSet<[u8; 32]>,
+    ) -> Result<(), Error> {
+        let mut data = Vec::with_capacity(32 + (verified.len() + corrupt.len()) * 32);
+        data.extend_from_slice(&VERIFY_CHECKPOINT_MAGIC_1_0);
+        data.extend_from_slice(&time.to_le_bytes());
+        data.extend_from_slice(&(verified.len() as u64).to_le_bytes());
+        data.extend_from_slice(&(corrupt.len() as u64).to_le_bytes());
+        for digest in verified.iter().chain(corrupt.iter()) {
+            data.extend_from_slice(digest);
+        }
+
+        replace_file(path, &data, CreateOptions::new(), false)
+    }
+}
+
+/// Where and since when the verify state of a [`VerifyWorker`] is checkpointed.
+struct CheckpointState {
+    path: PathBuf,
+    time: i64,
+    last_save: Mutex<Instant>,
+}
+
+/// Verification result of a single archive, see [`VerifyReport`].
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct VerifyArchiveReport {
+    /// The snapshot, including its namespace
+    pub snapshot: String,
+    /// The archive file name
+    pub archive: String,
+    /// Whether the archive verified successfully
+    pub ok: bool,
+    /// Number of chunks referenced by the index, 0 for blobs
+    pub chunks: u64,
+    /// Bytes read from the datastore
+    pub read_bytes: u64,
+    /// Bytes after decoding the chunks
+    pub decoded_bytes: u64,
+    /// Time spent on the archive in seconds
+    pub elapsed: f64,
+    /// The verification error, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Machine readable summary of a verification run, collected in addition to the task log.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct VerifyReport {
+    /// Number of verified snapshots
+    pub snapshots: u64,
+    /// Number of snapshots with verification errors
+    pub failed_snapshots: u64,
+    /// Number of snapshots skipped (filtered, locked or vanished)
+    pub skipped_snapshots: u64,
+    /// Results of all checked archives
+    pub archives: Vec<VerifyArchiveReport>,
+    /// Errors not related to a specific archive
+    pub errors: Vec<String>,
+}
+
+impl VerifyReport {
+    fn add_snapshot(&mut self, ok: bool) {
+        self.snapshots += 1;
+        if !ok {
+            self.failed_snapshots += 1;
+        }
+    }
+}
+
 /// A VerifyWorker encapsulates a task worker, datastore and information about which chunks have
 /// already been verified or detected as corrupt.
 pub struct VerifyWorker {
@@ -29,11 +166,27 @@ pub struct VerifyWorker {
     datastore: Arc<DataStore>,
     verified_chunks: Arc<Mutex<HashSet<[u8; 32]>>>,
     corrupt_chunks: Arc<Mutex<HashSet<[u8; 32]>>>,
+    // corrupt chunks verified again because of `recheck_corrupt`, at most once per run
+    rechecked_chunks: Mutex<HashSet<[u8; 32]>>,
+    worker_threads: usize,
+    dry_run: bool,
+    recheck_corrupt: bool,
+    read_limit: Option<u64>,
+    mode: VerifyMode,
+    report: Option<Arc<Mutex<VerifyReport>>>,
+    checkpoint: Option<CheckpointState>,
 }
 
 impl VerifyWorker {
     /// Creates a new VerifyWorker for a given task worker and datastore.
-    pub fn new(worker: Arc<dyn WorkerTaskContext>, datastore: Arc<DataStore>) -> Self {
+    ///
+    /// `worker_threads` sets the number of chunk decoder threads, if `None` it is derived from
+    /// the number of available CPUs.
+    pub fn new(
+        worker: Arc<dyn WorkerTaskContext>,
+        datastore: Arc<DataStore>,
+        worker_threads: Option<usize>,
+    ) -> Self {
         Self {
             worker,
             datastore,
@@ -41,8 +194,146 @@ impl VerifyWorker {
             verified_chunks: Arc::new(Mutex::new(HashSet::with_capacity(16 * 1024))),
             // start with 64 chunks since we assume there are few corrupt ones
             corrupt_chunks: Arc::new(Mutex::new(HashSet::with_capacity(64))),
+            rechecked_chunks: Mutex::new(HashSet::new()),
+            worker_threads: worker_threads.unwrap_or_else(default_worker_threads).max(1),
+            dry_run: false,
+            recheck_corrupt: false,
+            read_limit: None,
+            mode: VerifyMode::Full,
+            report: None,
+            checkpoint: None,
+        }
+    }
+
+    /// Only report errors, neither rename corrupt chunks nor update the verify state of
+    /// snapshots.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Verify chunks which were already detected as corrupt again, if a chunk file exists for
+    /// them (for example because a later backup re-uploaded it). Chunks which verify fine are
+    /// removed from the corrupt set. Each chunk is checked again at most once per run.
+    pub fn recheck_corrupt(mut self, recheck_corrupt: bool) -> Self {
+        self.recheck_corrupt = recheck_corrupt;
+        self
+    }
+
+    /// Limit reading chunks from the datastore to `read_limit` bytes per second. `None` or `0`
+    /// disable the limit.
+    pub fn read_limit(mut self, read_limit: Option<u64>) -> Self {
+        self.read_limit = read_limit.filter(|limit| *limit > 0);
+        self
+    }
+
+    /// Set what gets checked. With [`VerifyMode::Presence`] only the existence of chunk files is
+    /// checked, successful snapshots keep their previous verify state.
+    pub fn mode(mut self, mode: VerifyMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Collect a [`VerifyReport`] while verifying, see [`VerifyWorker::report`].
+    pub fn collect_report(mut self, collect_report: bool) -> Self {
+        self.report = collect_report.then(|| Arc::new(Mutex::new(VerifyReport::default())));
+        self
+    }
+
+    /// Returns the report collected so far, if enabled via [`VerifyWorker::collect_report`].
+    pub fn report(&self) -> Option<VerifyReport> {
+        self.report
+            .as_ref()
+            .map(|report| report.lock().unwrap().clone())
+    }
+
+    fn update_report(&self, update: impl FnOnce(&mut VerifyReport)) {
+        if let Some(report) = &self.report {
+            update(&mut report.lock().unwrap());
         }
     }
+
+    /// Persist the verified and corrupt chunks to `path`, so that a restarted run can skip
+    /// chunks verified before the interruption.
+    ///
+    /// An existing checkpoint is loaded if it is not older than `max_age` seconds, its chunks are
+    /// then not verified again. The age is counted from the start of the run which created it.
+    pub fn checkpoint(mut self, path: PathBuf, max_age: i64) -> Self {
+        let now = proxmox_time::epoch_i64();
+
+        let time = match VerifyCheckpoint::load(&path, max_age, now) {
+            Ok(Some(checkpoint)) => {
+                task_log!(
+                    self.worker,
+                    "resuming from checkpoint - {} verified, {} corrupt chunks",
+                    checkpoint.verified.len(),
+                    checkpoint.corrupt.len(),
+                );
+                self.verified_chunks
+                    .lock()
+                    .unwrap()
+                    .extend(checkpoint.verified);
+                self.corrupt_chunks
+                    .lock()
+                    .unwrap()
+                    .extend(checkpoint.corrupt);
+                checkpoint.time
+            }
+            Ok(None) => now,
+            Err(err) => {
+                task_log!(self.worker, "ignoring verify checkpoint - {}", err);
+                now
+            }
+        };
+
+        self.checkpoint = Some(CheckpointState {
+            path,
+            time,
+            last_save: Mutex::new(Instant::now()),
+        });
+        self
+    }
+
+    /// Write the checkpoint, if enabled. Unless `force` is set, this happens at most every
+    /// [`CHECKPOINT_INTERVAL`].
+    pub fn save_checkpoint(&self, force: bool) {
+        let checkpoint = match &self.checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => return,
+        };
+
+        let mut last_save = checkpoint.last_save.lock().unwrap();
+        if !force && last_save.elapsed() < CHECKPOINT_INTERVAL {
+            return;
+        }
+
+        let verified = self.verified_chunks.lock().unwrap().clone();
+        let corrupt = self.corrupt_chunks.lock().unwrap().clone();
+        if let Err(err) =
+            VerifyCheckpoint::save(&checkpoint.path, checkpoint.time, &verified, &corrupt)
+        {
+            task_log!(self.worker, "failed to save verify checkpoint - {}", err);
+        }
+        *last_save = Instant::now();
+    }
+
+    /// Remove the checkpoint after a completed run.
+    pub fn remove_checkpoint(&self) {
+        if let Some(checkpoint) = &self.checkpoint {
+            if let Err(err) = std::fs::remove_file(&checkpoint.path) {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    task_log!(self.worker, "failed to remove verify checkpoint - {}", err);
+                }
+            }
+        }
+    }
+}
+
+fn default_worker_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(DEFAULT_MAX_WORKER_THREADS)
 }
 
 fn verify_blob(backup_dir: &BackupDir, info: &FileInfo) -> Result<(), Error> {
@@ -73,9 +364,15 @@ fn rename_corrupted_chunk(
     datastore: Arc<DataStore>,
     digest: &[u8; 32],
     worker: &dyn WorkerTaskContext,
+    dry_run: bool,
 ) {
     let (path, digest_str) = datastore.chunk_path(digest);
 
+    if dry_run {
+        task_log!(worker, "dry-run: not renaming corrupted chunk {:?}", &path);
+        return;
+    }
+
     let mut counter = 0;
     let mut new_path = path.clone();
     loop {
@@ -105,13 +402,84 @@ fn rename_corrupted_chunk(
     };
 }
 
+/// Sleep until reading `read_bytes` since `start_time` does not exceed `limit` bytes per second.
+fn throttle_read(read_bytes: u64, limit: u64, start_time: Instant) {
+    let target = Duration::from_secs_f64(read_bytes as f64 / limit as f64);
+    let elapsed = start_time.elapsed();
+    if target > elapsed {
+        std::thread::sleep(target - elapsed);
+    }
+}
+
+/// Check that the chunk files referenced by `index` exist, without loading or decoding them.
+///
+/// Returns the digests of all missing chunks.
+pub fn verify_index_presence(datastore: &DataStore, index: &dyn IndexFile) -> Vec<[u8; 32]> {
+    let digests = (0..index.index_count()).filter_map(|pos| index.index_digest(pos));
+    missing_chunks(digests, |digest| {
+        let (path, _) = datastore.chunk_path(digest);
+        std::fs::metadata(path)
+            .map(|metadata| metadata.is_file())
+            .unwrap_or(false)
+    })
+}
+
+fn missing_chunks<'a>(
+    digests: impl Iterator<Item = &'a [u8; 32]>,
+    exists: impl Fn(&[u8; 32]) -> bool,
+) -> Vec<[u8; 32]> {
+    let mut checked = HashSet::new();
+    digests
+        .filter(|digest| checked.insert(**digest))
+        .filter(|digest| !exists(digest))
+        .copied()
+        .collect()
+}
+
+fn verify_index_chunks_presence(
+    verify_worker: &VerifyWorker,
+    index: &dyn IndexFile,
+    report: &mut VerifyArchiveReport,
+) -> Result<(), Error> {
+    report.chunks = index.index_count() as u64;
+
+    let missing = verify_index_presence(&verify_worker.datastore, index);
+    for digest in &missing {
+        task_log!(
+            verify_worker.worker,
+            "chunk {} is missing",
+            hex::encode(digest)
+        );
+    }
+
+    task_log!(
+        verify_worker.worker,
+        "  checked presence of {} chunks ({} missing)",
+        index.index_count(),
+        missing.len(),
+    );
+
+    if !missing.is_empty() {
+        bail!("{} chunks are missing", missing.len());
+    }
+
+    Ok(())
+}
+
 fn verify_index_chunks(
     verify_worker: &VerifyWorker,
     index: Box<dyn IndexFile + Send>,
     crypt_mode: CryptMode,
+    report: &mut VerifyArchiveReport,
 ) -> Result<(), Error> {
+    if verify_worker.mode == VerifyMode::Presence {
+        return verify_index_chunks_presence(verify_worker, &*index, report);
+    }
+
     let errors = Arc::new(AtomicUsize::new(0));
 
+    report.chunks = index.index_count() as u64;
+
     let start_time = Instant::now();
 
     let mut read_bytes = 0;
@@ -122,10 +490,11 @@ fn verify_index_chunks(
     let corrupt_chunks2 = Arc::clone(&verify_worker.corrupt_chunks);
     let verified_chunks2 = Arc::clone(&verify_worker.verified_chunks);
     let errors2 = Arc::clone(&errors);
+    let dry_run = verify_worker.dry_run;
 
     let decoder_pool = ParallelHandler::new(
         "verify chunk decoder",
-        4,
+        verify_worker.worker_threads,
         move |(chunk, digest, size): (DataBlob, [u8; 32], u64)| {
             let chunk_crypt_mode = match chunk.crypt_mode() {
                 Err(err) => {
@@ -151,9 +520,10 @@ fn verify_index_chunks(
                 corrupt_chunks2.lock().unwrap().insert(digest);
                 task_log!(worker2, "{}", err);
                 errors2.fetch_add(1, Ordering::SeqCst);
-                rename_corrupted_chunk(datastore2.clone(), &digest, &worker2);
+                rename_corrupted_chunk(datastore2.clone(), &digest, &worker2, dry_run);
             } else {
                 verified_chunks2.lock().unwrap().insert(digest);
+                corrupt_chunks2.lock().unwrap().remove(&digest);
             }
 
             Ok(())
@@ -174,6 +544,18 @@ fn verify_index_chunks(
             .unwrap()
             .contains(digest)
         {
+            if verify_worker.recheck_corrupt
+                && verify_worker.datastore.chunk_path(digest).0.exists()
+                && verify_worker
+                    .rechecked_chunks
+                    .lock()
+                    .unwrap()
+                    .insert(*digest)
+            {
+                // chunk got replaced since it was marked corrupt, verify it again
+                verify_worker.corrupt_chunks.lock().unwrap().remove(digest);
+                return false;
+            }
             let digest_str = hex::encode(digest);
             task_log!(
                 verify_worker.worker,
@@ -228,11 +610,15 @@ fn verify_index_chunks(
                     verify_worker.datastore.clone(),
                     &info.digest,
                     &verify_worker.worker,
+                    verify_worker.dry_run,
                 );
             }
             Ok(chunk) => {
                 let size = info.size();
                 read_bytes += chunk.raw_size();
+                if let Some(limit) = verify_worker.read_limit {
+                    throttle_read(read_bytes, limit, start_time);
+                }
                 decoder_pool.send((chunk, info.digest, size))?;
                 decoded_bytes += size;
             }
@@ -241,6 +627,9 @@ fn verify_index_chunks(
 
     decoder_pool.complete()?;
 
+    report.read_bytes = read_bytes;
+    report.decoded_bytes = decoded_bytes;
+
     let elapsed = start_time.elapsed().as_secs_f64();
 
     let read_bytes_mib = (read_bytes as f64) / (1024.0 * 1024.0);
@@ -273,6 +662,7 @@ fn verify_fixed_index(
     verify_worker: &VerifyWorker,
     backup_dir: &BackupDir,
     info: &FileInfo,
+    report: &mut VerifyArchiveReport,
 ) -> Result<(), Error> {
     let mut path = backup_dir.relative_path();
     path.push(&info.filename);
@@ -288,13 +678,19 @@ fn verify_fixed_index(
         bail!("wrong index checksum");
     }
 
-    verify_index_chunks(verify_worker, Box::new(index), info.chunk_crypt_mode())
+    verify_index_chunks(
+        verify_worker,
+        Box::new(index),
+        info.chunk_crypt_mode(),
+        report,
+    )
 }
 
 fn verify_dynamic_index(
     verify_worker: &VerifyWorker,
     backup_dir: &BackupDir,
     info: &FileInfo,
+    report: &mut VerifyArchiveReport,
 ) -> Result<(), Error> {
     let mut path = backup_dir.relative_path();
     path.push(&info.filename);
@@ -310,7 +706,123 @@ fn verify_dynamic_index(
         bail!("wrong index checksum");
     }
 
-    verify_index_chunks(verify_worker, Box::new(index), info.chunk_crypt_mode())
+    verify_index_chunks(
+        verify_worker,
+        Box::new(index),
+        info.chunk_crypt_mode(),
+        report,
+    )
+}
+
+/// Verify the given archives of a snapshot, returns the number of failed archives.
+///
+/// If the task gets aborted, the number of archives failed up to that point is returned together
+/// with the abort error.
+fn verify_files<'a>(
+    verify_worker: &VerifyWorker,
+    backup_dir: &BackupDir,
+    files: impl Iterator<Item = &'a FileInfo>,
+) -> (usize, Result<(), Error>) {
+    let mut error_count = 0;
+
+    for info in files {
+        let start_time = Instant::now();
+        let mut report = VerifyArchiveReport {
+            snapshot: print_ns_and_snapshot(backup_dir.backup_ns(), backup_dir.as_ref()),
+            archive: info.filename.clone(),
+            ..Default::default()
+        };
+
+        let result = proxmox_lang::try_block!({
+            task_log!(verify_worker.worker, "  check {}", info.filename);
+            match archive_type(&info.filename)? {
+                ArchiveType::FixedIndex => {
+                    verify_fixed_index(verify_worker, backup_dir, info, &mut report)
+                }
+                ArchiveType::DynamicIndex => {
+                    verify_dynamic_index(verify_worker, backup_dir, info, &mut report)
+                }
+                ArchiveType::Blob => verify_blob(backup_dir, info),
+            }
+        });
+
+        let abort_result = verify_worker
+            .worker
+            .check_abort()
+            .and_then(|_| verify_worker.worker.fail_on_shutdown());
+        if let Err(err) = abort_result {
+            return (error_count, Err(err));
+        }
+
+        report.ok = result.is_ok();
+        report.elapsed = start_time.elapsed().as_secs_f64();
+        if let Err(err) = &result {
+            report.error = Some(err.to_string());
+        }
+        verify_worker.update_report(|r| r.archives.push(report));
+
+        if let Err(err) = result {
+            task_log!(
+                verify_worker.worker,
+                "verify {}:{}/{} failed: {}",
+                verify_worker.datastore.name(),
+                backup_dir.dir(),
+                info.filename,
+                err,
+            );
+            error_count += 1;
+        }
+    }
+
+    (error_count, Ok(()))
+}
+
+/// Store the verify state in the unprotected part of the snapshot manifest.
+fn update_verify_state(
+    backup_dir: &BackupDir,
+    state: VerifyState,
+    aborted: bool,
+    upid: UPID,
+) -> Result<(), Error> {
+    let verify_state = SnapshotVerifyState {
+        state,
+        upid,
+        aborted,
+    };
+    let verify_state = serde_json::to_value(verify_state)?;
+    backup_dir
+        .update_manifest(|manifest| {
+            manifest.unprotected["verify_state"] = verify_state;
+        })
+        .map_err(|err| format_err!("unable to update manifest blob - {}", err))
+}
+
+/// Record a verification which got aborted before finding any error.
+///
+/// A snapshot which is already known to be corrupt keeps its failed state, so that it does not
+/// get used as base for incremental backups again just because a later verification got aborted.
+fn record_aborted_verify(backup_dir: &BackupDir, upid: UPID) -> Result<(), Error> {
+    let verify_state = serde_json::to_value(SnapshotVerifyState {
+        state: VerifyState::Failed,
+        upid,
+        aborted: true,
+    })?;
+    backup_dir
+        .update_manifest(|manifest| {
+            let previous = serde_json::from_value::<SnapshotVerifyState>(
+                manifest.unprotected["verify_state"].clone(),
+            );
+            if let Ok(SnapshotVerifyState {
+                state: VerifyState::Failed,
+                aborted: false,
+                ..
+            }) = previous
+            {
+                return;
+            }
+            manifest.unprotected["verify_state"] = verify_state;
+        })
+        .map_err(|err| format_err!("unable to update manifest blob - {}", err))
 }
 
 /// Verify a single backup snapshot
@@ -335,6 +847,7 @@ pub fn verify_backup_dir(
             verify_worker.datastore.name(),
             backup_dir.dir(),
         );
+        verify_worker.update_report(|r| r.skipped_snapshots += 1);
         return Ok(true);
     }
 
@@ -355,6 +868,7 @@ pub fn verify_backup_dir(
                 backup_dir.dir(),
                 err,
             );
+            verify_worker.update_report(|r| r.skipped_snapshots += 1);
             Ok(true)
         }
     }
@@ -378,6 +892,13 @@ pub fn verify_backup_dir_with_lock(
                 backup_dir.dir(),
                 err,
             );
+            verify_worker.update_report(|r| {
+                r.add_snapshot(false);
+                r.errors.push(format!(
+                    "{} - manifest load error: {err}",
+                    print_ns_and_snapshot(backup_dir.backup_ns(), backup_dir.as_ref()),
+                ));
+            });
             return Ok(false);
         }
     };
@@ -390,6 +911,7 @@ pub fn verify_backup_dir_with_lock(
                 verify_worker.datastore.name(),
                 backup_dir.dir(),
             );
+            verify_worker.update_report(|r| r.skipped_snapshots += 1);
             return Ok(true);
         }
     }
@@ -401,46 +923,151 @@ pub fn verify_backup_dir_with_lock(
         backup_dir.dir()
     );
 
-    let mut error_count = 0;
+    let (error_count, abort_result) =
+        verify_files(verify_worker, backup_dir, manifest.files().iter());
 
-    let mut verify_result = VerifyState::Ok;
-    for info in manifest.files() {
-        let result = proxmox_lang::try_block!({
-            task_log!(verify_worker.worker, "  check {}", info.filename);
-            match archive_type(&info.filename)? {
-                ArchiveType::FixedIndex => verify_fixed_index(verify_worker, backup_dir, info),
-                ArchiveType::DynamicIndex => verify_dynamic_index(verify_worker, backup_dir, info),
-                ArchiveType::Blob => verify_blob(backup_dir, info),
-            }
-        });
+    if let Err(err) = abort_result {
+        // record the interruption, so that a previous verify state does not stay around
+        let result = if verify_worker.dry_run {
+            Ok(())
+        } else if error_count > 0 {
+            update_verify_state(backup_dir, VerifyState::Failed, false, upid)
+        } else if verify_worker.mode == VerifyMode::Presence {
+            Ok(())
+        } else {
+            record_aborted_verify(backup_dir, upid)
+        };
+        if let Err(err) = result {
+            task_log!(verify_worker.worker, "{}", err);
+        }
+        return Err(err);
+    }
 
-        verify_worker.worker.check_abort()?;
-        verify_worker.worker.fail_on_shutdown()?;
+    verify_worker.update_report(|r| r.add_snapshot(error_count == 0));
 
-        if let Err(err) = result {
+    let verify_result = if error_count == 0 {
+        VerifyState::Ok
+    } else {
+        VerifyState::Failed
+    };
+
+    if verify_worker.dry_run {
+        task_log!(
+            verify_worker.worker,
+            "dry-run: not updating verify state of {}:{} ({:?})",
+            verify_worker.datastore.name(),
+            backup_dir.dir(),
+            verify_result,
+        );
+        return Ok(error_count == 0);
+    }
+
+    if verify_worker.mode == VerifyMode::Presence && error_count == 0 {
+        task_log!(
+            verify_worker.worker,
+            "presence check: not updating verify state of {}:{}",
+            verify_worker.datastore.name(),
+            backup_dir.dir(),
+        );
+        return Ok(true);
+    }
+
+    update_verify_state(backup_dir, verify_result, false, upid)?;
+
+    Ok(error_count == 0)
+}
+
+/// Verify selected archives of a single backup snapshot
+///
+/// Only the archives listed in `archive_names` are checked, names not contained in the manifest
+/// are logged and ignored. Since not all archives get checked, a successful run does not update
+/// the verify state of the snapshot, but failures mark it as failed.
+///
+/// Returns
+/// - Ok(true) if verify is successful
+/// - Ok(false) if there were verification errors
+/// - Err(_) if task was aborted
+pub fn verify_backup_dir_files(
+    verify_worker: &VerifyWorker,
+    backup_dir: &BackupDir,
+    archive_names: &[String],
+    upid: UPID,
+) -> Result<bool, Error> {
+    let _snap_lock = match lock_dir_noblock_shared(
+        &backup_dir.full_path(),
+        "snapshot",
+        "locked by another operation",
+    ) {
+        Ok(snap_lock) => snap_lock,
+        Err(err) => {
             task_log!(
                 verify_worker.worker,
-                "verify {}:{}/{} failed: {}",
+                "SKIPPED: verify {}:{} - could not acquire snapshot lock: {}",
                 verify_worker.datastore.name(),
                 backup_dir.dir(),
-                info.filename,
                 err,
             );
-            error_count += 1;
-            verify_result = VerifyState::Failed;
+            verify_worker.update_report(|r| r.skipped_snapshots += 1);
+            return Ok(true);
         }
-    }
+    };
 
-    let verify_state = SnapshotVerifyState {
-        state: verify_result,
-        upid,
+    let manifest = match backup_dir.load_manifest() {
+        Ok((manifest, _)) => manifest,
+        Err(err) => {
+            task_log!(
+                verify_worker.worker,
+                "verify {}:{} - manifest load error: {}",
+                verify_worker.datastore.name(),
+                backup_dir.dir(),
+                err,
+            );
+            verify_worker.update_report(|r| {
+                r.add_snapshot(false);
+                r.errors.push(format!(
+                    "{} - manifest load error: {err}",
+                    print_ns_and_snapshot(backup_dir.backup_ns(), backup_dir.as_ref()),
+                ));
+            });
+            return Ok(false);
+        }
     };
-    let verify_state = serde_json::to_value(verify_state)?;
-    backup_dir
-        .update_manifest(|manifest| {
-            manifest.unprotected["verify_state"] = verify_state;
-        })
-        .map_err(|err| format_err!("unable to update manifest blob - {}", err))?;
+
+    for name in archive_names {
+        if manifest.lookup_file_info(name).is_err() {
+            task_log!(
+                verify_worker.worker,
+                "WARN: verify {}:{} - archive '{}' not found in manifest, skipping",
+                verify_worker.datastore.name(),
+                backup_dir.dir(),
+                name,
+            );
+        }
+    }
+
+    task_log!(
+        verify_worker.worker,
+        "verify {}:{} (archives: {})",
+        verify_worker.datastore.name(),
+        backup_dir.dir(),
+        archive_names.join(", "),
+    );
+
+    let files = manifest
+        .files()
+        .iter()
+        .filter(|info| archive_names.contains(&info.filename));
+
+    let (error_count, abort_result) = verify_files(verify_worker, backup_dir, files);
+    if abort_result.is_ok() {
+        verify_worker.update_report(|r| r.add_snapshot(error_count == 0));
+    }
+
+    if error_count > 0 && !verify_worker.dry_run {
+        update_verify_state(backup_dir, VerifyState::Failed, false, upid)?;
+    }
+
+    abort_result?;
 
     Ok(error_count == 0)
 }
@@ -495,6 +1122,7 @@ pub fn verify_backup_group(
         }
         progress.done_snapshots = pos as u64 + 1;
         task_log!(verify_worker.worker, "percentage done: {}", progress);
+        verify_worker.save_checkpoint(false);
     }
 
     Ok(errors)
@@ -505,7 +1133,8 @@ pub fn verify_backup_group(
 /// Errors are logged to the worker log.
 ///
 /// Returns
-/// - Ok(failed_dirs) where failed_dirs had verification errors
+/// - Ok((failed_dirs, report)) where failed_dirs had verification errors, and report is the
+///   [`VerifyReport`] if enabled via [`VerifyWorker::collect_report`]
 /// - Err(_) if task was aborted
 pub fn verify_all_backups(
     verify_worker: &VerifyWorker,
@@ -514,7 +1143,7 @@ pub fn verify_all_backups(
     max_depth: Option<usize>,
     owner: Option<&Authid>,
     filter: Option<&dyn Fn(&BackupManifest) -> bool>,
-) -> Result<Vec<String>, Error> {
+) -> Result<(Vec<String>, Option<VerifyReport>), Error> {
     let mut errors = Vec::new();
     let worker = Arc::clone(&verify_worker.worker);
 
@@ -554,6 +1183,7 @@ pub fn verify_all_backups(
                 Err(err) => {
                     // we don't filter by owner, but we want to log the error
                     task_log!(worker, "error on iterating groups in ns '{ns}' - {err}");
+                    verify_worker.update_report(|r| r.errors.push(err.to_string()));
                     errors.push(err.to_string());
                     None
                 }
@@ -564,7 +1194,8 @@ pub fn verify_all_backups(
             .collect::<Vec<BackupGroup>>(),
         Err(err) => {
             task_log!(worker, "unable to list backups: {}", err,);
-            return Ok(errors);
+            verify_worker.update_report(|r| r.errors.push(err.to_string()));
+            return Ok((errors, verify_worker.report()));
         }
     };
 
@@ -585,7 +1216,23 @@ pub fn verify_all_backups(
         errors.append(&mut group_errors);
     }
 
-    Ok(errors)
+    Ok((errors, verify_worker.report()))
+}
+
+/// Log a summary of `report` and the report itself as single line JSON to the worker log.
+pub fn log_verify_report(worker: &dyn WorkerTaskContext, report: &VerifyReport) {
+    task_log!(
+        worker,
+        "verified {} snapshots ({} failed, {} skipped), {} archives",
+        report.snapshots,
+        report.failed_snapshots,
+        report.skipped_snapshots,
+        report.archives.len(),
+    );
+    match serde_json::to_string(report) {
+        Ok(report) => task_log!(worker, "verify report: {}", report),
+        Err(err) => task_log!(worker, "unable to serialize verify report - {}", err),
+    }
 }
 
 /// Filter out any snapshot from being (re-)verified where this fn returns false.
@@ -601,6 +1248,7 @@ pub fn verify_filter(
     let raw_verify_state = manifest.unprotected["verify_state"].clone();
     match serde_json::from_value::<SnapshotVerifyState>(raw_verify_state) {
         Err(_) => true, // no last verification, always include
+        Ok(last_verify) if last_verify.aborted => true, // never completed
         Ok(last_verify) => {
             match outdated_after {
                 None => false, // never re-verify if ignored and no max age
@@ -614,3 +1262,569 @@ pub fn verify_filter(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_verify_filter_aborted() -> Result<(), Error> {
+        let mut manifest = BackupManifest::new("vm/100/2024-01-01T00:00:00Z".parse()?);
+        assert!(verify_filter(true, None, &manifest));
+
+        let upid: UPID =
+            "UPID:pbs:000039A4:00C1ABD0:00000000:65920000:verify:store:root@pam:".parse()?;
+        for (state, aborted, expected) in [
+            (VerifyState::Ok, false, false),
+            (VerifyState::Failed, false, false),
+            (VerifyState::Failed, true, true),
+        ] {
+            let verify_state = SnapshotVerifyState {
+                state,
+                upid: upid.clone(),
+                aborted,
+            };
+            manifest.unprotected["verify_state"] = serde_json::to_value(verify_state)?;
+            assert_eq!(verify_filter(true, None, &manifest), expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_chunks() {
+        let present = [[1u8; 32], [2u8; 32]];
+        let digests = [[1u8; 32], [3u8; 32], [2u8; 32], [3u8; 32]];
+
+        let missing = missing_chunks(digests.iter(), |digest| present.contains(digest));
+        assert_eq!(missing, vec![[3u8; 32]]);
+
+        let missing = missing_chunks(present.iter(), |digest| present.contains(digest));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_verify_checkpoint() -> Result<(), Error> {
+        let mut path = std::fs::canonicalize(".")?; // we need absolute path
+        path.push(".testdir-verify-checkpoint");
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path)?;
+        let file = path.join("verificationjob-test.checkpoint");
+
+        let start = 1_700_000_000;
+        assert!(VerifyCheckpoint::load(&file, 3600, start)?.is_none());
+
+        let verified: HashSet<[u8; 32]> = [[1u8; 32], [2u8; 32], [3u8; 32]].into();
+        let corrupt: HashSet<[u8; 32]> = [[4u8; 32]].into();
+        VerifyCheckpoint::save(&file, start, &verified, &corrupt)?;
+
+        // a second run within the time window skips the verified chunks
+        let checkpoint = VerifyCheckpoint::load(&file, 3600, start + 600)?.unwrap();
+        assert_eq!(checkpoint.time, start);
+        assert_eq!(checkpoint.verified, verified);
+        assert_eq!(checkpoint.corrupt, corrupt);
+
+        // stale checkpoints are ignored
+        assert!(VerifyCheckpoint::load(&file, 3600, start + 3601)?.is_none());
+
+        let data = std::fs::read(&file)?;
+
+        std::fs::write(&file, b"garbage")?;
+        assert!(VerifyCheckpoint::load(&file, 3600, start).is_err());
+
+        // truncated digest list
+        std::fs::write(&file, &data[..data.len() - 16])?;
+        assert!(VerifyCheckpoint::load(&file, 3600, start).is_err());
+
+        // truncated header
+        std::fs::write(&file, &data[..20])?;
+        assert!(VerifyCheckpoint::load(&file, 3600, start).is_err());
+
+        // chunk counts overflowing the expected size
+        let mut header = data[..32].to_vec();
+        header[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        header[24..32].copy_from_slice(&2u64.to_le_bytes());
+        std::fs::write(&file, &header)?;
+        assert!(VerifyCheckpoint::load(&file, 3600, start).is_err());
+        header[16..24].copy_from_slice(&(1u64 << 59).to_le_bytes());
+        header[24..32].copy_from_slice(&0u64.to_le_bytes());
+        std::fs::write(&file, &header)?;
+        assert!(VerifyCheckpoint::load(&file, 3600, start).is_err());
+
+        // garbage start time
+        header[8..16].copy_from_slice(&i64::MIN.to_le_bytes());
+        std::fs::write(&file, &header)?;
+        assert!(VerifyCheckpoint::load(&file, 3600, start)?.is_none());
+
+        let _ = std::fs::remove_dir_all(&path);
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct TestWorker {
+        // request an abort once the abort state got checked this many times
+        abort_after: Option<usize>,
+        abort_checks: AtomicUsize,
+    }
+
+    impl WorkerTaskContext for TestWorker {
+        fn abort_requested(&self) -> bool {
+            let checks = self.abort_checks.fetch_add(1, Ordering::SeqCst);
+            matches!(self.abort_after, Some(abort_after) if checks >= abort_after)
+        }
+
+        fn shutdown_requested(&self) -> bool {
+            false
+        }
+
+        fn log(&self, _level: log::Level, _message: &std::fmt::Arguments) {}
+    }
+
+    const TEST_CHUNK_SIZE: usize = 4096;
+
+    /// A datastore below the current directory, removed again on drop.
+    struct TestStore {
+        path: PathBuf,
+        datastore: Arc<DataStore>,
+    }
+
+    impl TestStore {
+        fn new(name: &str) -> Result<Self, Error> {
+            let mut path = std::fs::canonicalize(".")?; // we need absolute path
+            path.push(format!(".testdir-{name}"));
+            let _ = std::fs::remove_dir_all(&path);
+
+            pbs_datastore::ChunkStore::create(
+                name,
+                &path,
+                nix::unistd::Uid::current(),
+                nix::unistd::Gid::current(),
+                None,
+                pbs_api_types::DatastoreFSyncLevel::None,
+            )?;
+            let datastore = unsafe { DataStore::open_path(name, &path, None)? };
+
+            Ok(Self { path, datastore })
+        }
+
+        /// Insert a chunk filled with `fill`, returns its digest.
+        fn insert_chunk(&self, fill: u8) -> Result<[u8; 32], Error> {
+            let data = [fill; TEST_CHUNK_SIZE];
+            let digest = openssl::sha::sha256(&data);
+            self.datastore
+                .insert_chunk(&DataBlob::encode(&data, None, false)?, &digest)?;
+            Ok(digest)
+        }
+
+        /// Replace the chunk file of `digest` by a valid blob with other content, so that the
+        /// chunk fails to verify.
+        fn corrupt_chunk(&self, digest: &[u8; 32]) -> Result<(), Error> {
+            let (path, _) = self.datastore.chunk_path(digest);
+            let blob = DataBlob::encode(&[0xffu8; TEST_CHUNK_SIZE], None, false)?;
+            std::fs::write(path, blob.raw_data())?;
+            Ok(())
+        }
+
+        /// Create the snapshot `snapshot` with a single fixed index referencing `digests`.
+        fn create_snapshot(
+            &self,
+            snapshot: &str,
+            digests: &[[u8; 32]],
+            verify_state: Option<&SnapshotVerifyState>,
+        ) -> Result<BackupDir, Error> {
+            let snapshot: pbs_api_types::BackupDir = snapshot.parse()?;
+            let backup_dir = self
+                .datastore
+                .backup_dir(BackupNamespace::root(), snapshot.clone())?;
+            std::fs::create_dir_all(backup_dir.full_path())?;
+
+            let mut index_path = backup_dir.relative_path();
+            index_path.push("drive-scsi0.img.fidx");
+            let mut writer = self.datastore.create_fixed_writer(
+                &index_path,
+                digests.len() * TEST_CHUNK_SIZE,
+                TEST_CHUNK_SIZE,
+            )?;
+            for (pos, digest) in digests.iter().enumerate() {
+                writer.add_digest(pos, digest)?;
+            }
+            writer.close()?;
+            let (csum, size) = self
+                .datastore
+                .open_fixed_reader(&index_path)?
+                .compute_csum();
+
+            let mut manifest = BackupManifest::new(snapshot);
+            manifest.add_file(
+                "drive-scsi0.img.fidx".to_string(),
+                size,
+                csum,
+                CryptMode::None,
+            )?;
+            if let Some(verify_state) = verify_state {
+                manifest.unprotected["verify_state"] = serde_json::to_value(verify_state)?;
+            }
+            let blob = DataBlob::encode(manifest.to_string(None)?.as_bytes(), None, true)?;
+            std::fs::write(
+                backup_dir
+                    .full_path()
+                    .join(pbs_datastore::manifest::MANIFEST_BLOB_NAME),
+                blob.raw_data(),
+            )?;
+
+            Ok(backup_dir)
+        }
+
+        fn verify_state(&self, backup_dir: &BackupDir) -> Result<serde_json::Value, Error> {
+            let (manifest, _) = backup_dir.load_manifest()?;
+            Ok(manifest.unprotected["verify_state"].clone())
+        }
+    }
+
+    impl Drop for TestStore {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+
+    fn test_upid() -> UPID {
+        "UPID:pbs:000039A4:00C1ABD0:00000000:65920000:verify:test:root@pam:"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_verify_dry_run() -> Result<(), Error> {
+        let store = TestStore::new("verify-dry-run")?;
+
+        // one good and one corrupt chunk
+        let digests = [store.insert_chunk(1)?, store.insert_chunk(2)?];
+        store.corrupt_chunk(&digests[1])?;
+
+        let verify_state = SnapshotVerifyState {
+            state: VerifyState::Ok,
+            upid: test_upid(),
+            aborted: false,
+        };
+        let backup_dir =
+            store.create_snapshot("vm/100/2024-01-01T00:00:00Z", &digests, Some(&verify_state))?;
+
+        let mut chunk_files = Vec::new();
+        for digest in &digests {
+            let (path, _) = store.datastore.chunk_path(digest);
+            let modified = std::fs::metadata(&path)?.modified()?;
+            chunk_files.push((path.clone(), std::fs::read(&path)?, modified));
+        }
+
+        let verify_worker = VerifyWorker::new(
+            Arc::<TestWorker>::default(),
+            store.datastore.clone(),
+            Some(1),
+        )
+        .dry_run(true);
+        assert!(!verify_backup_dir(
+            &verify_worker,
+            &backup_dir,
+            test_upid(),
+            None
+        )?);
+
+        assert_eq!(
+            store.verify_state(&backup_dir)?,
+            serde_json::to_value(&verify_state)?
+        );
+
+        for (path, data, modified) in chunk_files {
+            assert_eq!(std::fs::read(&path)?, data);
+            assert_eq!(std::fs::metadata(&path)?.modified()?, modified);
+
+            let bad_files = std::fs::read_dir(path.parent().unwrap())?
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().ends_with(".bad"))
+                .count();
+            assert_eq!(bad_files, 0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_report_counts() -> Result<(), Error> {
+        let store = TestStore::new("verify-report")?;
+
+        let verified_state = SnapshotVerifyState {
+            state: VerifyState::Ok,
+            upid: test_upid(),
+            aborted: false,
+        };
+        let good = [store.insert_chunk(1)?, store.insert_chunk(2)?];
+        let corrupt = [store.insert_chunk(3)?, store.insert_chunk(4)?];
+        store.corrupt_chunk(&corrupt[1])?;
+
+        store.create_snapshot("vm/100/2024-01-01T00:00:00Z", &good, Some(&verified_state))?;
+        store.create_snapshot("vm/100/2024-01-02T00:00:00Z", &good, None)?;
+        store.create_snapshot("vm/100/2024-01-03T00:00:00Z", &corrupt, None)?;
+
+        let verify_worker = VerifyWorker::new(
+            Arc::<TestWorker>::default(),
+            store.datastore.clone(),
+            Some(1),
+        )
+        .collect_report(true);
+        let group =
+            store
+                .datastore
+                .backup_group_from_parts(BackupNamespace::root(), BackupType::Vm, "100");
+        let failed_dirs = verify_backup_group(
+            &verify_worker,
+            &group,
+            &mut StoreProgress::new(1),
+            &test_upid(),
+            Some(&|manifest| verify_filter(true, None, manifest)),
+        )?;
+        assert_eq!(failed_dirs, ["vm/100/2024-01-03T00:00:00Z"]);
+
+        let report = verify_worker.report().unwrap();
+        assert_eq!(report.snapshots, 2);
+        assert_eq!(report.failed_snapshots, 1);
+        assert_eq!(report.skipped_snapshots, 1);
+        assert!(report.errors.is_empty());
+
+        // newest snapshot first
+        assert_eq!(report.archives.len(), 2);
+        let failed = &report.archives[0];
+        assert_eq!(failed.snapshot, "vm/100/2024-01-03T00:00:00Z");
+        assert_eq!(failed.archive, "drive-scsi0.img.fidx");
+        assert!(!failed.ok);
+        assert_eq!(failed.chunks, 2);
+        assert!(failed.error.is_some());
+        let ok = &report.archives[1];
+        assert_eq!(ok.snapshot, "vm/100/2024-01-02T00:00:00Z");
+        assert!(ok.ok);
+        assert_eq!(ok.chunks, 2);
+        assert_eq!(ok.decoded_bytes, 2 * TEST_CHUNK_SIZE as u64);
+        assert!(ok.error.is_none());
+
+        let value = serde_json::to_value(&report)?;
+        assert_eq!(value["failed-snapshots"], 1);
+        assert_eq!(value["archives"][0]["ok"], false);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_recheck_corrupt() -> Result<(), Error> {
+        let store = TestStore::new("verify-recheck-corrupt")?;
+
+        let digest = store.insert_chunk(1)?;
+        store.corrupt_chunk(&digest)?;
+        let backup_dir = store.create_snapshot("vm/100/2024-01-01T00:00:00Z", &[digest], None)?;
+
+        let verify_worker = VerifyWorker::new(
+            Arc::<TestWorker>::default(),
+            store.datastore.clone(),
+            Some(1),
+        )
+        .recheck_corrupt(true);
+
+        // the corrupt chunk gets renamed, replace it by a good copy like a new backup would
+        assert!(!verify_backup_dir(
+            &verify_worker,
+            &backup_dir,
+            test_upid(),
+            None
+        )?);
+        assert!(verify_worker
+            .corrupt_chunks
+            .lock()
+            .unwrap()
+            .contains(&digest));
+        assert!(!store.datastore.chunk_path(&digest).0.exists());
+        store.insert_chunk(1)?;
+
+        assert!(verify_backup_dir(
+            &verify_worker,
+            &backup_dir,
+            test_upid(),
+            None
+        )?);
+        assert!(!verify_worker
+            .corrupt_chunks
+            .lock()
+            .unwrap()
+            .contains(&digest));
+        assert!(verify_worker
+            .verified_chunks
+            .lock()
+            .unwrap()
+            .contains(&digest));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_recheck_corrupt_once() -> Result<(), Error> {
+        let store = TestStore::new("verify-recheck-corrupt-once")?;
+
+        // with dry-run the corrupt chunk stays in place
+        let digest = store.insert_chunk(1)?;
+        store.corrupt_chunk(&digest)?;
+        let snapshots = [
+            "vm/100/2024-01-01T00:00:00Z",
+            "vm/100/2024-01-02T00:00:00Z",
+            "vm/100/2024-01-03T00:00:00Z",
+        ];
+
+        let verify_worker = VerifyWorker::new(
+            Arc::<TestWorker>::default(),
+            store.datastore.clone(),
+            Some(1),
+        )
+        .dry_run(true)
+        .recheck_corrupt(true)
+        .collect_report(true);
+        for snapshot in snapshots {
+            let backup_dir = store.create_snapshot(snapshot, &[digest], None)?;
+            assert!(!verify_backup_dir(
+                &verify_worker,
+                &backup_dir,
+                test_upid(),
+                None
+            )?);
+        }
+
+        // the first snapshot detects the corruption, the second one checks again, the third
+        // one does not load the chunk anymore
+        let read_bytes: Vec<u64> = verify_worker
+            .report()
+            .unwrap()
+            .archives
+            .iter()
+            .map(|archive| archive.read_bytes)
+            .collect();
+        assert_eq!(read_bytes.len(), 3);
+        assert!(read_bytes[0] > 0);
+        assert_eq!(read_bytes[1], read_bytes[0]);
+        assert_eq!(read_bytes[2], 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_resume_checkpoint() -> Result<(), Error> {
+        let store = TestStore::new("verify-resume-checkpoint")?;
+        let checkpoint = store.path.join("verify.checkpoint");
+
+        let digests = [store.insert_chunk(1)?, store.insert_chunk(2)?];
+        let backup_dir = store.create_snapshot("vm/100/2024-01-01T00:00:00Z", &digests, None)?;
+
+        let verify_worker = VerifyWorker::new(
+            Arc::<TestWorker>::default(),
+            store.datastore.clone(),
+            Some(1),
+        )
+        .checkpoint(checkpoint.clone(), 3600);
+        assert!(verify_backup_dir(
+            &verify_worker,
+            &backup_dir,
+            test_upid(),
+            None
+        )?);
+        verify_worker.save_checkpoint(true);
+
+        // without any chunk files left, only skipping them lets the second run succeed
+        for digest in &digests {
+            std::fs::remove_file(store.datastore.chunk_path(digest).0)?;
+        }
+
+        let verify_worker = VerifyWorker::new(
+            Arc::<TestWorker>::default(),
+            store.datastore.clone(),
+            Some(1),
+        )
+        .checkpoint(checkpoint, 3600)
+        .collect_report(true);
+        assert!(verify_backup_dir(
+            &verify_worker,
+            &backup_dir,
+            test_upid(),
+            None
+        )?);
+
+        let report = verify_worker.report().unwrap();
+        assert_eq!(report.archives.len(), 1);
+        assert!(report.archives[0].ok);
+        assert_eq!(report.archives[0].read_bytes, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_aborted() -> Result<(), Error> {
+        let store = TestStore::new("verify-aborted")?;
+
+        let digests = [
+            store.insert_chunk(1)?,
+            store.insert_chunk(2)?,
+            store.insert_chunk(3)?,
+            store.insert_chunk(4)?,
+        ];
+
+        let verify = |backup_dir: &BackupDir| {
+            let worker = TestWorker {
+                abort_after: Some(2),
+                ..Default::default()
+            };
+            let verify_worker =
+                VerifyWorker::new(Arc::new(worker), store.datastore.clone(), Some(1));
+            verify_backup_dir(&verify_worker, backup_dir, test_upid(), None)
+        };
+
+        // a verified snapshot is marked as aborted, so that it gets verified again
+        let ok_state = SnapshotVerifyState {
+            state: VerifyState::Ok,
+            upid: test_upid(),
+            aborted: false,
+        };
+        let backup_dir =
+            store.create_snapshot("vm/100/2024-01-01T00:00:00Z", &digests, Some(&ok_state))?;
+        assert!(verify(&backup_dir).is_err());
+        let verify_state: SnapshotVerifyState =
+            serde_json::from_value(store.verify_state(&backup_dir)?)?;
+        assert!(verify_state.state == VerifyState::Failed);
+        assert!(verify_state.aborted);
+
+        // a snapshot known to be corrupt stays failed
+        let failed_state = SnapshotVerifyState {
+            state: VerifyState::Failed,
+            upid: test_upid(),
+            aborted: false,
+        };
+        let backup_dir =
+            store.create_snapshot("vm/101/2024-01-01T00:00:00Z", &digests, Some(&failed_state))?;
+        assert!(verify(&backup_dir).is_err());
+        assert_eq!(
+            store.verify_state(&backup_dir)?,
+            serde_json::to_value(&failed_state)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_throttle_read() {
+        let limit = 4 * 1024 * 1024;
+        let start_time = Instant::now();
+
+        let mut read_bytes = 0;
+        for _ in 0..16 {
+            read_bytes += 64 * 1024;
+            throttle_read(read_bytes, limit, start_time);
+        }
+
+        // 1 MiB at 4 MiB/s
+        let elapsed = start_time.elapsed().as_secs_f64();
+        assert!(elapsed >= 0.25);
+        assert!((read_bytes as f64 / elapsed) <= limit as f64 * 1.01);
+    }
+}
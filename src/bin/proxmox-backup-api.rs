@@ -137,6 +137,7 @@ async fn run() -> Result<(), Error> {
         command_sock.spawn()?;
         proxmox_rest_server::catch_shutdown_signal()?;
         proxmox_rest_server::catch_reload_signal()?;
+        proxmox_backup::server::catch_terminate_signal()?;
         Ok(())
     });
 
@@ -44,7 +44,7 @@ use proxmox_time::CalendarEvent;
 
 use pbs_api_types::{
     Authid, DataStoreConfig, Operation, PruneJobConfig, SyncJobConfig, TapeBackupJobConfig,
-    VerificationJobConfig,
+    VerificationJobConfig, WorkerType,
 };
 
 use proxmox_rest_server::daemon;
@@ -353,6 +353,7 @@ async fn run() -> Result<(), Error> {
         command_sock.spawn()?;
         proxmox_rest_server::catch_shutdown_signal()?;
         proxmox_rest_server::catch_reload_signal()?;
+        server::catch_terminate_signal()?;
         Ok(())
     });
 
@@ -515,7 +516,7 @@ async fn schedule_datastore_garbage_collection() {
             }
         }
 
-        let worker_type = "garbage_collection";
+        let worker_type = WorkerType::GarbageCollection.as_str();
 
         let last = match jobstate::last_run_time(worker_type, &store) {
             Ok(time) => time,
@@ -534,9 +535,20 @@ async fn schedule_datastore_garbage_collection() {
             }
         };
 
+        let delay = match store_config.gc_randomized_delay {
+            Some(ref max_delay) => match jobstate::compute_randomized_delay(&store, max_delay) {
+                Ok(delay) => delay,
+                Err(err) => {
+                    eprintln!("unable to compute randomized delay '{max_delay}' - {err}");
+                    0
+                }
+            },
+            None => 0,
+        };
+
         let now = proxmox_time::epoch_i64();
 
-        if next > now {
+        if next + delay > now {
             continue;
         }
 
@@ -592,9 +604,9 @@ async fn schedule_datastore_prune_jobs() {
             continue; // no 'keep' values set, keep all
         }
 
-        let worker_type = "prunejob";
+        let worker_type = WorkerType::PruneJob.as_str();
         let auth_id = Authid::root_auth_id().clone();
-        if check_schedule(worker_type, &job_config.schedule, &job_id) {
+        if check_schedule(worker_type, &job_config.schedule, &job_id, None) {
             let job = match Job::new(worker_type, &job_id) {
                 Ok(job) => job,
                 Err(_) => continue, // could not get lock
@@ -635,8 +647,8 @@ async fn schedule_datastore_sync_jobs() {
             None => continue,
         };
 
-        let worker_type = "syncjob";
-        if check_schedule(worker_type, &event_str, &job_id) {
+        let worker_type = WorkerType::SyncJob.as_str();
+        if check_schedule(worker_type, &event_str, &job_id, None) {
             let job = match Job::new(worker_type, &job_id) {
                 Ok(job) => job,
                 Err(_) => continue, // could not get lock
@@ -671,9 +683,10 @@ async fn schedule_datastore_verify_jobs() {
             None => continue,
         };
 
-        let worker_type = "verificationjob";
+        let worker_type = WorkerType::VerificationJob.as_str();
         let auth_id = Authid::root_auth_id().clone();
-        if check_schedule(worker_type, &event_str, &job_id) {
+        let randomized_delay = job_config.randomized_delay.as_deref();
+        if check_schedule(worker_type, &event_str, &job_id, randomized_delay) {
             let job = match Job::new(worker_type, &job_id) {
                 Ok(job) => job,
                 Err(_) => continue, // could not get lock
@@ -707,9 +720,9 @@ async fn schedule_tape_backup_jobs() {
             None => continue,
         };
 
-        let worker_type = "tape-backup-job";
+        let worker_type = WorkerType::TapeBackupJob.as_str();
         let auth_id = Authid::root_auth_id().clone();
-        if check_schedule(worker_type, &event_str, &job_id) {
+        if check_schedule(worker_type, &event_str, &job_id, None) {
             let job = match Job::new(worker_type, &job_id) {
                 Ok(job) => job,
                 Err(_) => continue, // could not get lock
@@ -724,13 +737,13 @@ async fn schedule_tape_backup_jobs() {
 }
 
 async fn schedule_task_log_rotate() {
-    let worker_type = "logrotate";
+    let worker_type = WorkerType::LogRotate.as_str();
     let job_id = "access-log_and_task-archive";
 
     // schedule daily at 00:00 like normal logrotate
     let schedule = "00:00";
 
-    if !check_schedule(worker_type, schedule, job_id) {
+    if !check_schedule(worker_type, schedule, job_id, None) {
         // if we never ran the rotation, schedule instantly
         match jobstate::JobState::load(worker_type, job_id) {
             Ok(jobstate::JobState::Created { .. }) => {}
@@ -1221,7 +1234,12 @@ fn rrd_update_disk_stat(disk: &DiskStat, rrd_prefix: &str) {
     }
 }
 
-fn check_schedule(worker_type: &str, event_str: &str, id: &str) -> bool {
+fn check_schedule(
+    worker_type: &str,
+    event_str: &str,
+    id: &str,
+    randomized_delay: Option<&str>,
+) -> bool {
     let event: CalendarEvent = match event_str.parse() {
         Ok(event) => event,
         Err(err) => {
@@ -1247,8 +1265,19 @@ fn check_schedule(worker_type: &str, event_str: &str, id: &str) -> bool {
         }
     };
 
+    let delay = match randomized_delay {
+        Some(max_delay) => match jobstate::compute_randomized_delay(id, max_delay) {
+            Ok(delay) => delay,
+            Err(err) => {
+                eprintln!("unable to compute randomized delay '{max_delay}' - {err}");
+                0
+            }
+        },
+        None => 0,
+    };
+
     let now = proxmox_time::epoch_i64();
-    next <= now
+    next + delay <= now
 }
 
 fn gather_disk_stats(disk_manager: Arc<DiskManage>, path: &Path, name: &str) -> DiskStat {
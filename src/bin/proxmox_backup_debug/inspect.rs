@@ -4,7 +4,6 @@ use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
 use anyhow::{bail, format_err, Error};
-use hex::FromHex;
 use serde_json::{json, Value};
 use walkdir::WalkDir;
 
@@ -21,8 +20,8 @@ use pbs_datastore::file_formats::{
     ENCR_COMPR_BLOB_MAGIC_1_0, FIXED_SIZED_CHUNK_INDEX_1_0, UNCOMPRESSED_BLOB_MAGIC_1_0,
 };
 use pbs_datastore::fixed_index::FixedIndexReader;
-use pbs_datastore::index::IndexFile;
-use pbs_datastore::DataBlob;
+use pbs_datastore::index::{parse_digest, IndexFile};
+use pbs_datastore::{DataBlob, DataBlobReader};
 use pbs_key_config::load_and_decrypt_key;
 use pbs_tools::crypt_config::CryptConfig;
 
@@ -115,9 +114,7 @@ fn inspect_chunk(
     };
 
     let digest_raw: Option<[u8; 32]> = digest
-        .map(|ref d| {
-            <[u8; 32]>::from_hex(d).map_err(|e| format_err!("could not parse chunk - {}", e))
-        })
+        .map(|ref d| parse_digest(d).map_err(|e| format_err!("could not parse chunk - {}", e)))
         .map_or(Ok(None), |r| r.map(Some))?;
 
     let search_path = reference_filter.as_ref().map(Path::new);
@@ -331,12 +328,100 @@ fn inspect_file(
     Ok(())
 }
 
+/// Human readable name of a blob magic number
+fn blob_type_name(magic: &[u8; 8]) -> Option<&'static str> {
+    match *magic {
+        UNCOMPRESSED_BLOB_MAGIC_1_0 => Some("uncompressed"),
+        COMPRESSED_BLOB_MAGIC_1_0 => Some("compressed"),
+        ENCRYPTED_BLOB_MAGIC_1_0 => Some("encrypted"),
+        ENCR_COMPR_BLOB_MAGIC_1_0 => Some("encrypted-compressed"),
+        _ => None,
+    }
+}
+
+/// Collects the header information of a blob, no key is needed for this
+///
+/// The decoded size is only included for unencrypted blobs with a valid CRC, the payload is
+/// streamed through the decoder for that and not kept in memory.
+fn blob_header_info(blob: &DataBlob) -> Result<Value, Error> {
+    let blob_type = blob_type_name(blob.magic())
+        .ok_or_else(|| format_err!("unknown blob magic {:?}", blob.magic()))?;
+
+    let crc_ok = blob.verify_crc().is_ok();
+
+    let mut val = json!({
+        "type": blob_type,
+        "crc": blob.crc(),
+        "crc-ok": crc_ok,
+        "is-encrypted": blob.is_encrypted(),
+        "is-compressed": blob.is_compressed(),
+        "size": blob.raw_size(),
+    });
+
+    if crc_ok && !blob.is_encrypted() {
+        let mut reader = DataBlobReader::new(blob.raw_data(), None, None)?;
+        std::io::copy(&mut reader, &mut std::io::sink())?;
+        val["decoded-size"] = reader.decoded_size().into();
+    }
+
+    Ok(val)
+}
+
+#[api(
+    input: {
+        properties: {
+            file: {
+                description: "Path to the blob or chunk file.",
+                type: String,
+            },
+            "output-format": {
+                schema: OUTPUT_FORMAT,
+                optional: true,
+            },
+        }
+    }
+)]
+/// Inspect the header of a blob or chunk file, without decoding or decrypting it
+fn inspect_blob(file: String, param: Value) -> Result<(), Error> {
+    let output_format = get_output_format(&param);
+
+    // don't use load_from_reader, a CRC mismatch should be reported, not fail
+    let raw_data =
+        std::fs::read(Path::new(&file)).map_err(|e| format_err!("could not read file - {}", e))?;
+    let blob = DataBlob::from_raw(raw_data)?;
+
+    let val = blob_header_info(&blob)?;
+
+    if output_format == "text" {
+        println!("type: {}", val["type"]);
+        println!(
+            "CRC: {}({})",
+            val["crc"],
+            if val["crc-ok"] == true { "OK" } else { "BAD" }
+        );
+        println!("is-encrypted: {}", val["is-encrypted"]);
+        println!("is-compressed: {}", val["is-compressed"]);
+        println!("size: {}", val["size"]);
+        if !val["decoded-size"].is_null() {
+            println!("decoded size: {}", val["decoded-size"]);
+        }
+    } else {
+        format_and_print_result(&val, &output_format);
+    }
+
+    Ok(())
+}
+
 pub fn inspect_commands() -> CommandLineInterface {
     let cmd_def = CliCommandMap::new()
         .insert(
             "chunk",
             CliCommand::new(&API_METHOD_INSPECT_CHUNK).arg_param(&["chunk"]),
         )
+        .insert(
+            "blob",
+            CliCommand::new(&API_METHOD_INSPECT_BLOB).arg_param(&["file"]),
+        )
         .insert(
             "file",
             CliCommand::new(&API_METHOD_INSPECT_FILE).arg_param(&["file"]),
@@ -344,3 +429,42 @@ pub fn inspect_commands() -> CommandLineInterface {
 
     cmd_def.into()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_blob_header_info() -> Result<(), Error> {
+        let data = vec![0u8; 64 * 1024];
+
+        let blob = DataBlob::encode(&data, None, true)?;
+        let val = blob_header_info(&blob)?;
+        assert_eq!(val["type"], "compressed");
+        assert_eq!(val["crc"], blob.crc());
+        assert_eq!(val["crc-ok"], true);
+        assert_eq!(val["is-encrypted"], false);
+        assert_eq!(val["is-compressed"], true);
+        assert_eq!(val["size"], blob.raw_size());
+        assert_eq!(val["decoded-size"], data.len() as u64);
+
+        let crypt_config = CryptConfig::new([1u8; 32])?;
+        let blob = DataBlob::encode(&data, Some(&crypt_config), false)?;
+        let val = blob_header_info(&blob)?;
+        assert_eq!(val["type"], "encrypted");
+        assert_eq!(val["crc"], blob.crc());
+        assert_eq!(val["crc-ok"], true);
+        assert_eq!(val["is-encrypted"], true);
+        assert_eq!(val["is-compressed"], false);
+        assert_eq!(val["size"], blob.raw_size());
+        assert!(val["decoded-size"].is_null());
+
+        let mut raw_data = blob.into_inner();
+        let last = raw_data.len() - 1;
+        raw_data[last] ^= 1;
+        let val = blob_header_info(&DataBlob::from_raw(raw_data)?)?;
+        assert_eq!(val["crc-ok"], false);
+
+        Ok(())
+    }
+}
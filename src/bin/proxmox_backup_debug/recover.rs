@@ -7,12 +7,13 @@ use anyhow::{bail, format_err, Error};
 use proxmox_router::cli::{CliCommand, CliCommandMap, CommandLineInterface};
 use proxmox_schema::api;
 
+use pbs_api_types::{Operation, DATASTORE_SCHEMA};
 use pbs_client::tools::key_source::get_encryption_key_password;
-use pbs_datastore::dynamic_index::DynamicIndexReader;
+use pbs_datastore::dynamic_index::{DynamicIndexReader, DynamicIndexWriter};
 use pbs_datastore::file_formats::{DYNAMIC_SIZED_CHUNK_INDEX_1_0, FIXED_SIZED_CHUNK_INDEX_1_0};
 use pbs_datastore::fixed_index::FixedIndexReader;
-use pbs_datastore::index::IndexFile;
-use pbs_datastore::DataBlob;
+use pbs_datastore::index::{parse_digest, IndexFile};
+use pbs_datastore::{DataBlob, DataStore};
 use pbs_key_config::load_and_decrypt_key;
 use pbs_tools::crypt_config::CryptConfig;
 
@@ -191,10 +192,208 @@ fn recover_index(
     Ok(())
 }
 
-pub fn recover_commands() -> CommandLineInterface {
-    let cmd_def = CliCommandMap::new().insert(
-        "index",
-        CliCommand::new(&API_METHOD_RECOVER_INDEX).arg_param(&["file", "chunks"]),
+/// Parses a list of hex encoded chunk digests, one per line. Empty lines and lines starting
+/// with '#' are ignored.
+fn parse_digest_list(content: &str) -> Result<Vec<[u8; 32]>, Error> {
+    content
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(nr, line)| parse_digest(line).map_err(|err| format_err!("line {} - {err}", nr + 1)))
+        .collect()
+}
+
+/// Writes a dynamic index referencing `digests` in the given order. The chunk offsets are
+/// computed from the decoded chunk sizes, so every chunk has to decode and match its digest.
+///
+/// Returns the size of the indexed data and the index checksum.
+fn write_dynamic_index(
+    mut writer: DynamicIndexWriter,
+    digests: &[[u8; 32]],
+    load_chunk: impl Fn(&[u8; 32]) -> Result<DataBlob, Error>,
+    crypt_config: Option<&CryptConfig>,
+) -> Result<(u64, [u8; 32]), Error> {
+    let mut offset = 0u64;
+
+    for digest in digests {
+        let digest_str = hex::encode(digest);
+        let data = load_chunk(digest)
+            .and_then(|chunk| chunk.decode(crypt_config, Some(digest)))
+            .map_err(|err| format_err!("chunk {digest_str} cannot be used - {err}"))?;
+
+        offset += data.len() as u64;
+        writer.add_chunk(offset, digest)?;
+    }
+
+    let csum = writer.close()?;
+
+    Ok((offset, csum))
+}
+
+#[api(
+    input: {
+        properties: {
+            store: {
+                schema: DATASTORE_SCHEMA,
+            },
+            digests: {
+                description: "Path to a file with the hex encoded chunk digests, one per line, \
+                    in the order of the original data.",
+                type: String,
+            },
+            "output-path": {
+                description: "Path of the dynamic index (.didx) to write, relative to the \
+                    datastore or absolute.",
+                type: String,
+            },
+            "keyfile": {
+                description: "Path to a keyfile, needed to decode encrypted chunks.",
+                type: String,
+                optional: true,
+            },
+        }
+    }
+)]
+/// Recreate a dynamic index file from a list of chunk digests.
+///
+/// Each chunk is loaded from the datastore and decoded to determine its size, which gives the
+/// offsets stored in the index.
+fn recover_dynamic_index(
+    store: String,
+    digests: String,
+    output_path: String,
+    keyfile: Option<String>,
+) -> Result<(), Error> {
+    if !output_path.ends_with(".didx") {
+        bail!("output path must have a .didx extension");
+    }
+
+    let digests = parse_digest_list(&std::fs::read_to_string(&digests)?)?;
+    if digests.is_empty() {
+        bail!("digest list is empty");
+    }
+
+    let crypt_config = match keyfile {
+        Some(keyfile) => {
+            let (key, _created, _fingerprint) =
+                load_and_decrypt_key(Path::new(&keyfile), &get_encryption_key_password)?;
+            Some(CryptConfig::new(key)?)
+        }
+        None => None,
+    };
+
+    let datastore = DataStore::lookup_datastore(&store, Some(Operation::Write))?;
+    let writer = datastore.create_dynamic_writer(&output_path)?;
+
+    let (size, csum) = write_dynamic_index(
+        writer,
+        &digests,
+        |digest| datastore.load_chunk(digest),
+        crypt_config.as_ref(),
+    )?;
+
+    println!(
+        "wrote index {output_path:?} with {} chunks, {size} bytes, checksum {}",
+        digests.len(),
+        hex::encode(csum),
     );
+
+    Ok(())
+}
+
+pub fn recover_commands() -> CommandLineInterface {
+    let cmd_def = CliCommandMap::new()
+        .insert(
+            "index",
+            CliCommand::new(&API_METHOD_RECOVER_INDEX).arg_param(&["file", "chunks"]),
+        )
+        .insert(
+            "dynamic-index",
+            CliCommand::new(&API_METHOD_RECOVER_DYNAMIC_INDEX).arg_param(&[
+                "store",
+                "digests",
+                "output-path",
+            ]),
+        );
     cmd_def.into()
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use pbs_api_types::DatastoreFSyncLevel;
+    use pbs_datastore::data_blob::DataChunkBuilder;
+    use pbs_datastore::ChunkStore;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_digest_list() {
+        let list = format!("# chunks\n{}\n\n  {}\n", "00".repeat(32), "ff".repeat(32));
+        let digests = parse_digest_list(&list).unwrap();
+        assert_eq!(digests, vec![[0u8; 32], [0xffu8; 32]]);
+
+        assert!(parse_digest_list("0011").is_err());
+    }
+
+    #[test]
+    fn test_recover_dynamic_index() -> Result<(), Error> {
+        let mut path = std::fs::canonicalize(".")?; // we need absolute path
+        path.push(".testdir-recover-didx");
+
+        if let Err(_e) = std::fs::remove_dir_all(&path) { /* ignore */ }
+
+        let user = nix::unistd::User::from_uid(nix::unistd::Uid::current())?.unwrap();
+        let store = Arc::new(ChunkStore::create(
+            "test",
+            &path,
+            user.uid,
+            user.gid,
+            None,
+            DatastoreFSyncLevel::None,
+        )?);
+
+        let mut original = DynamicIndexWriter::create(store.clone(), Path::new("orig.didx"))?;
+        let mut digests = Vec::new();
+        let mut offset = 0;
+        for (fill, size) in [(1u8, 4096), (2u8, 1000), (3u8, 65536)] {
+            let (chunk, digest) = DataChunkBuilder::new(&vec![fill; size]).build()?;
+            store.insert_chunk(&chunk, &digest)?;
+            offset += size as u64;
+            original.add_chunk(offset, &digest)?;
+            digests.push(digest);
+        }
+        let original_csum = original.close()?;
+
+        let writer = DynamicIndexWriter::create(store.clone(), Path::new("recovered.didx"))?;
+        let load_chunk = |digest: &[u8; 32]| {
+            let (chunk_path, _) = store.chunk_path(digest);
+            DataBlob::load_from_reader(&mut File::open(chunk_path)?)
+        };
+        let (size, csum) = write_dynamic_index(writer, &digests, load_chunk, None)?;
+
+        assert_eq!(size, offset);
+        assert_eq!(csum, original_csum);
+
+        let original = DynamicIndexReader::open(&path.join("orig.didx"))?;
+        let recovered = DynamicIndexReader::open(&path.join("recovered.didx"))?;
+        assert_eq!(recovered.index_count(), original.index_count());
+        for pos in 0..original.index_count() {
+            let (a, b) = (original.chunk_info(pos), recovered.chunk_info(pos));
+            assert_eq!(
+                a.map(|i| (i.range, i.digest)),
+                b.map(|i| (i.range, i.digest))
+            );
+        }
+
+        // a missing chunk must fail
+        let writer = DynamicIndexWriter::create(store.clone(), Path::new("missing.didx"))?;
+        assert!(write_dynamic_index(writer, &[[0u8; 32]], load_chunk, None).is_err());
+
+        if let Err(_e) = std::fs::remove_dir_all(&path) { /* ignore */ }
+
+        Ok(())
+    }
+}
@@ -44,7 +44,8 @@ fn list_prune_jobs(param: Value, rpcenv: &mut dyn RpcEnvironment) -> Result<Valu
         .column(ColumnConfig::new("keep-daily"))
         .column(ColumnConfig::new("keep-weekly"))
         .column(ColumnConfig::new("keep-monthly"))
-        .column(ColumnConfig::new("keep-yearly"));
+        .column(ColumnConfig::new("keep-yearly"))
+        .column(ColumnConfig::new("keep-last-verified"));
 
     format_and_print_result_full(&mut data, &info.returns, &output_format, &options);
 
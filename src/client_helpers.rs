@@ -7,18 +7,70 @@ use proxmox_auth_api::ticket::Ticket;
 
 use crate::auth::private_auth_keyring;
 
+/// Returns true if `host` refers to this node via the loopback interface.
+fn is_local_host(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "::1" | "[::1]")
+}
+
+/// Connect to a Proxmox Backup Server
+///
+/// Explicitly passed `options` are used as is. Otherwise a ticket is created automatically if
+/// run as 'root' user and connecting to this node as root@pam, else the password is queried
+/// interactively.
+pub fn connect_to(
+    host: &str,
+    port: u16,
+    auth_id: &Authid,
+    options: Option<HttpClientOptions>,
+) -> Result<HttpClient, Error> {
+    let options = match options {
+        Some(options) => options,
+        None if is_local_host(host)
+            && auth_id == Authid::root_auth_id()
+            && nix::unistd::Uid::current().is_root() =>
+        {
+            let ticket =
+                Ticket::new("PBS", Userid::root_userid())?.sign(private_auth_keyring(), None)?;
+            let fingerprint = crate::cert_info()?.fingerprint()?;
+            HttpClientOptions::new_non_interactive(ticket, Some(fingerprint))
+        }
+        None => HttpClientOptions::new_interactive(None, None),
+    };
+
+    HttpClient::new(host, port, auth_id, options)
+}
+
 /// Connect to localhost:8007 as root@pam
 ///
 /// This automatically creates a ticket if run as 'root' user.
-pub fn connect_to_localhost() -> Result<pbs_client::HttpClient, Error> {
-    let options = if nix::unistd::Uid::current().is_root() {
-        let ticket =
-            Ticket::new("PBS", Userid::root_userid())?.sign(private_auth_keyring(), None)?;
-        let fingerprint = crate::cert_info()?.fingerprint()?;
-        HttpClientOptions::new_non_interactive(ticket, Some(fingerprint))
-    } else {
-        HttpClientOptions::new_interactive(None, None)
-    };
+pub fn connect_to_localhost() -> Result<HttpClient, Error> {
+    connect_to("localhost", 8007, Authid::root_auth_id(), None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_local_host() {
+        assert!(is_local_host("localhost"));
+        assert!(is_local_host("127.0.0.1"));
+        assert!(is_local_host("[::1]"));
+        assert!(!is_local_host("pbs.example.com"));
+        assert!(!is_local_host("192.168.1.10"));
+    }
+
+    #[test]
+    fn test_connect_to_remote() -> Result<(), Error> {
+        let auth_id: Authid = "sync@pbs!remote".parse()?;
+        let fingerprint = "aa:".repeat(31) + "aa";
+        let options =
+            HttpClientOptions::new_non_interactive("secret".to_string(), Some(fingerprint));
+
+        let client = connect_to("pbs.example.com", 8008, &auth_id, Some(options))?;
+        assert_eq!(client.server(), "pbs.example.com");
+        assert_eq!(client.port(), 8008);
 
-    HttpClient::new("localhost", 8007, Authid::root_auth_id(), options)
+        Ok(())
+    }
 }
@@ -44,7 +44,7 @@ use serde::{Deserialize, Serialize};
 
 use proxmox_sys::fs::{create_path, file_read_optional_string, replace_file, CreateOptions};
 
-use proxmox_time::CalendarEvent;
+use proxmox_time::{CalendarEvent, TimeSpan};
 
 use pbs_api_types::{JobScheduleStatus, UPID};
 use pbs_buildcfg::PROXMOX_BACKUP_STATE_DIR_M;
@@ -99,6 +99,13 @@ fn get_path(jobtype: &str, jobname: &str) -> PathBuf {
     path
 }
 
+/// Path of the file a job may use to persist its progress, e.g. verified chunks
+pub fn checkpoint_path(jobtype: &str, jobname: &str) -> PathBuf {
+    let mut path = get_path(jobtype, jobname);
+    path.set_extension("checkpoint");
+    path
+}
+
 fn get_lock<P>(path: P) -> Result<BackupLockGuard, Error>
 where
     P: AsRef<Path>,
@@ -117,6 +124,11 @@ pub fn remove_state_file(jobtype: &str, jobname: &str) -> Result<(), Error> {
             bail!("cannot remove statefile for {jobtype} - {jobname}: {err}");
         }
     }
+    if let Err(err) = std::fs::remove_file(checkpoint_path(jobtype, jobname)) {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            bail!("cannot remove checkpoint for {jobtype} - {jobname}: {err}");
+        }
+    }
     path.set_extension("lck");
     if let Err(err) = std::fs::remove_file(&path) {
         if err.kind() != std::io::ErrorKind::NotFound {
@@ -341,3 +353,44 @@ pub fn compute_schedule_status(
 
     Ok(status)
 }
+
+/// Computes a random delay in seconds of at most `max_delay` (a time span like `1h 30min`).
+///
+/// The delay is derived from `id`, so it stays the same for a job across restarts, while
+/// different jobs sharing the same schedule get spread out.
+pub fn compute_randomized_delay(id: &str, max_delay: &str) -> Result<i64, Error> {
+    let max_delay: TimeSpan = max_delay.parse()?;
+    let max_delay = f64::from(max_delay) as u64;
+    if max_delay == 0 {
+        return Ok(0);
+    }
+
+    let digest = openssl::sha::sha256(id.as_bytes());
+    let seed = u64::from_le_bytes(digest[..8].try_into().unwrap());
+
+    Ok((seed % (max_delay + 1)) as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_randomized_delay() -> Result<(), Error> {
+        assert_eq!(compute_randomized_delay("job1", "0s")?, 0);
+
+        for id in ["job1", "job2", "store1", "a-much-longer-job-id"] {
+            let delay = compute_randomized_delay(id, "1h")?;
+            assert!((0..=3600).contains(&delay), "delay {delay} out of bounds");
+            // must be stable for the same id
+            assert_eq!(delay, compute_randomized_delay(id, "1h")?);
+        }
+
+        let delay = compute_randomized_delay("job1", "30s")?;
+        assert!((0..=30).contains(&delay), "delay {delay} out of bounds");
+
+        assert!(compute_randomized_delay("job1", "not a time span").is_err());
+
+        Ok(())
+    }
+}
@@ -92,3 +92,27 @@ pub fn create_active_operations_dir() -> Result<(), Error> {
         .map_err(|err: Error| format_err!("unable to create active operations dir - {err}"))?;
     Ok(())
 }
+
+/// Handle SIGTERM like SIGINT, i.e. request a shutdown of the daemon.
+///
+/// systemd sends SIGTERM when stopping a service. Instead of duplicating the
+/// shutdown logic, the signal gets forwarded as SIGINT to ourselves, so it is
+/// handled by the handler installed via `proxmox_rest_server::catch_shutdown_signal()`.
+pub fn catch_terminate_signal() -> Result<(), Error> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut stream = signal(SignalKind::terminate())?;
+
+    tokio::spawn(async move {
+        while stream.recv().await.is_some() {
+            log::info!("got terminate request (SIGTERM)");
+            if let Err(err) = kill(Pid::this(), Signal::SIGINT) {
+                log::error!("unable to forward SIGTERM as shutdown request - {err}");
+            }
+        }
+    });
+
+    Ok(())
+}
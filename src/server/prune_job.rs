@@ -117,6 +117,7 @@ pub(crate) fn cli_keep_options(opts: &mut Vec<String>, options: &KeepOptions) {
         ("weekly", options.keep_weekly),
         ("monthly", options.keep_monthly),
         ("yearly", options.keep_yearly),
+        ("last-verified", options.keep_last_verified),
     ] {
         match keep {
             Some(count) if count > 0 => opts.push(format!("--keep-{key} {count}")),
@@ -12,7 +12,7 @@ use http::StatusCode;
 use proxmox_human_byte::HumanByte;
 use proxmox_rest_server::WorkerTask;
 use proxmox_router::HttpError;
-use proxmox_sys::{task_log, task_warn};
+use proxmox_sys::{task_log, task_warn, WorkerTaskContext};
 use serde_json::json;
 
 use pbs_api_types::{
@@ -1138,6 +1138,8 @@ async fn pull_group(
     let mut pull_stats = PullStats::default();
 
     for (pos, from_snapshot) in list.into_iter().enumerate() {
+        check_pull_abort(worker)?;
+
         let to_snapshot = params
             .target
             .store
@@ -1151,6 +1153,7 @@ async fn pull_group(
             pull_snapshot_from(worker, reader, &to_snapshot, downloaded_chunks.clone()).await;
 
         progress.done_snapshots = pos as u64 + 1;
+        worker.progress(progress.percentage());
         task_log!(worker, "percentage done: {}", progress);
 
         let stats = result?; // stop on error
@@ -1353,6 +1356,8 @@ pub(crate) async fn pull_store(
     let mut pull_stats = PullStats::default();
 
     for namespace in namespaces {
+        check_pull_abort(worker)?;
+
         let source_store_ns_str = print_store_and_ns(params.source.get_store(), &namespace);
 
         let target_ns = namespace.map_prefix(&params.source.get_ns(), &params.target.ns)?;
@@ -1467,65 +1472,39 @@ pub(crate) async fn pull_ns(
         unfiltered_count
     );
 
-    let mut errors = false;
-
     let mut new_groups = HashSet::new();
     for group in list.iter() {
         new_groups.insert(group.clone());
     }
 
-    let mut progress = StoreProgress::new(list.len() as u64);
-    let mut pull_stats = PullStats::default();
-
     let target_ns = namespace.map_prefix(&params.source.get_ns(), &params.target.ns)?;
 
-    for (done, group) in list.into_iter().enumerate() {
-        progress.done_groups = done as u64;
-        progress.done_snapshots = 0;
-        progress.group_snapshots = 0;
+    let params: &PullParameters = params;
+    let target_ns_ref = &target_ns;
+    let (progress, mut pull_stats, mut errors) =
+        pull_groups(worker, list, |group, mut progress| async move {
+            worker.progress(progress.percentage());
 
-        let (owner, _lock_guard) =
-            match params
-                .target
-                .store
-                .create_locked_backup_group(&target_ns, &group, &params.owner)
-            {
+            let (owner, _lock_guard) = match params.target.store.create_locked_backup_group(
+                target_ns_ref,
+                &group,
+                &params.owner,
+            ) {
                 Ok(result) => result,
-                Err(err) => {
-                    task_log!(
-                        worker,
-                        "sync group {} failed - group lock failed: {}",
-                        &group,
-                        err
-                    );
-                    errors = true;
-                    // do not stop here, instead continue
-                    task_log!(worker, "create_locked_backup_group failed");
-                    continue;
-                }
+                Err(err) => return (progress, Err(format_err!("group lock failed: {err}"))),
             };
 
-        // permission check
-        if params.owner != owner {
-            // only the owner is allowed to create additional snapshots
-            task_log!(
-                worker,
-                "sync group {} failed - owner check failed ({} != {})",
-                &group,
-                params.owner,
-                owner
-            );
-            errors = true; // do not stop here, instead continue
-        } else {
-            match pull_group(worker, params, namespace, &group, &mut progress).await {
-                Ok(stats) => pull_stats.add(stats),
-                Err(err) => {
-                    task_log!(worker, "sync group {} failed - {}", &group, err,);
-                    errors = true; // do not stop here, instead continue
-                }
+            // permission check
+            if params.owner != owner {
+                // only the owner is allowed to create additional snapshots
+                let err = format_err!("owner check failed ({} != {})", params.owner, owner);
+                return (progress, Err(err));
             }
-        }
-    }
+
+            let result = pull_group(worker, params, namespace, &group, &mut progress).await;
+            (progress, result)
+        })
+        .await?;
 
     if params.remove_vanished {
         let result: Result<(), Error> = proxmox_lang::try_block!({
@@ -1584,3 +1563,141 @@ pub(crate) async fn pull_ns(
 
     Ok((progress, pull_stats, errors))
 }
+
+/// Pull `groups` one after another, `pull_group` gets the progress and has to return it again.
+///
+/// A failed group is logged and does not stop the sync, the returned flag is set then. An aborted
+/// task or daemon shutdown stops it before the next group.
+async fn pull_groups<F, Fut>(
+    worker: &dyn WorkerTaskContext,
+    groups: Vec<BackupGroup>,
+    mut pull_group: F,
+) -> Result<(StoreProgress, PullStats, bool), Error>
+where
+    F: FnMut(BackupGroup, StoreProgress) -> Fut,
+    Fut: std::future::Future<Output = (StoreProgress, Result<PullStats, Error>)>,
+{
+    let mut progress = StoreProgress::new(groups.len() as u64);
+    let mut pull_stats = PullStats::default();
+    let mut errors = false;
+
+    for (done, group) in groups.into_iter().enumerate() {
+        // a failed group does not stop the sync, so check here as well
+        check_pull_abort(worker)?;
+
+        progress.done_groups = done as u64;
+        progress.done_snapshots = 0;
+        progress.group_snapshots = 0;
+
+        let (group_progress, result) = pull_group(group.clone(), progress).await;
+        progress = group_progress;
+        match result {
+            Ok(stats) => pull_stats.add(stats),
+            Err(err) => {
+                task_log!(worker, "sync group {} failed - {}", &group, err);
+                errors = true; // do not stop here, instead continue
+            }
+        }
+    }
+
+    Ok((progress, pull_stats, errors))
+}
+
+/// Fails if the task got aborted or the daemon is shutting down, so that a long running sync
+/// can be interrupted between snapshots, groups and namespaces.
+fn check_pull_abort(worker: &dyn WorkerTaskContext) -> Result<(), Error> {
+    worker.check_abort()?;
+    worker.fail_on_shutdown()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::atomic::AtomicBool;
+
+    struct TestWorker {
+        abort: AtomicBool,
+        shutdown: AtomicBool,
+    }
+
+    impl WorkerTaskContext for TestWorker {
+        fn abort_requested(&self) -> bool {
+            self.abort.load(Ordering::SeqCst)
+        }
+
+        fn shutdown_requested(&self) -> bool {
+            self.shutdown.load(Ordering::SeqCst)
+        }
+
+        fn log(&self, _level: log::Level, _message: &std::fmt::Arguments) {}
+    }
+
+    #[test]
+    fn test_check_pull_abort() -> Result<(), Error> {
+        let worker = TestWorker {
+            abort: AtomicBool::new(false),
+            shutdown: AtomicBool::new(false),
+        };
+
+        check_pull_abort(&worker)?;
+
+        worker.abort.store(true, Ordering::SeqCst);
+        assert!(check_pull_abort(&worker).is_err());
+
+        worker.abort.store(false, Ordering::SeqCst);
+        worker.shutdown.store(true, Ordering::SeqCst);
+        assert!(check_pull_abort(&worker).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pull_groups_abort() -> Result<(), Error> {
+        let worker = TestWorker {
+            abort: AtomicBool::new(false),
+            shutdown: AtomicBool::new(false),
+        };
+        let groups: Vec<BackupGroup> = ["vm/100", "vm/101", "vm/102"]
+            .iter()
+            .map(|group| group.parse())
+            .collect::<Result<_, _>>()?;
+        let pulled = Mutex::new(Vec::new());
+
+        // a failed group does not stop the sync
+        let (progress, _, errors) = proxmox_async::runtime::block_on(pull_groups(
+            &worker,
+            groups.clone(),
+            |group, progress| {
+                let result = if group.id == "100" {
+                    Err(format_err!("pull failed"))
+                } else {
+                    Ok(PullStats::default())
+                };
+                pulled.lock().unwrap().push(group);
+                async move { (progress, result) }
+            },
+        ))?;
+        assert!(errors);
+        assert_eq!(progress.done_groups, 2);
+        assert_eq!(*pulled.lock().unwrap(), groups);
+
+        // an abort while pulling the second group stops the sync before the third one
+        pulled.lock().unwrap().clear();
+        let result = proxmox_async::runtime::block_on(pull_groups(
+            &worker,
+            groups.clone(),
+            |group, progress| {
+                if group.id == "101" {
+                    worker.abort.store(true, Ordering::SeqCst);
+                }
+                pulled.lock().unwrap().push(group);
+                async move { (progress, Ok(PullStats::default())) }
+            },
+        ));
+        assert!(result.is_err());
+        assert_eq!(*pulled.lock().unwrap(), groups[..2]);
+
+        Ok(())
+    }
+}
@@ -6,8 +6,8 @@ use proxmox_rest_server::WorkerTask;
 use proxmox_sys::task_log;
 
 use crate::{
-    backup::{verify_all_backups, verify_filter},
-    server::jobstate::Job,
+    backup::{log_verify_report, verify_all_backups, verify_filter},
+    server::jobstate::{self, Job},
 };
 
 /// Runs a verification job.
@@ -44,7 +44,17 @@ pub fn do_verification_job(
                 None => Default::default(),
             };
 
-            let verify_worker = crate::backup::VerifyWorker::new(worker.clone(), datastore);
+            let mut verify_worker = crate::backup::VerifyWorker::new(
+                worker.clone(),
+                datastore,
+                verification_job.worker_threads,
+            )
+            .read_limit(verification_job.read_limit.map(|limit| limit.as_u64()))
+            .collect_report(true);
+            if let Some(max_age) = verification_job.checkpoint_max_age {
+                let path = jobstate::checkpoint_path(job.jobtype(), job.jobname());
+                verify_worker = verify_worker.checkpoint(path, max_age * 3600);
+            }
             let result = verify_all_backups(
                 &verify_worker,
                 worker.upid(),
@@ -54,7 +64,18 @@ pub fn do_verification_job(
                 Some(&move |manifest| {
                     verify_filter(ignore_verified_snapshots, outdated_after, manifest)
                 }),
-            );
+            )
+            .map(|(failed_dirs, report)| {
+                if let Some(report) = report {
+                    log_verify_report(&*worker, &report);
+                }
+                failed_dirs
+            });
+            match result {
+                Ok(_) => verify_worker.remove_checkpoint(),
+                Err(_) => verify_worker.save_checkpoint(true),
+            }
+
             let job_result = match result {
                 Ok(ref failed_dirs) if failed_dirs.is_empty() => Ok(()),
                 Ok(ref failed_dirs) => {
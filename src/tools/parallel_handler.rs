@@ -158,3 +158,43 @@ impl<I> Drop for ParallelHandler<I> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+
+    use super::*;
+
+    #[test]
+    fn test_thread_count_respected() -> Result<(), Error> {
+        let threads = 3;
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_active = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(threads));
+
+        let active2 = Arc::clone(&active);
+        let max_active2 = Arc::clone(&max_active);
+        let barrier2 = Arc::clone(&barrier);
+
+        let pool = ParallelHandler::new("test pool", threads, move |i: usize| {
+            let now = active2.fetch_add(1, Ordering::SeqCst) + 1;
+            max_active2.fetch_max(now, Ordering::SeqCst);
+            if i < threads {
+                // only passes once the first items are handled by all threads at the same time
+                barrier2.wait();
+            }
+            active2.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        });
+
+        for i in 0..32 {
+            pool.send(i)?;
+        }
+        pool.complete()?;
+
+        assert_eq!(max_active.load(Ordering::SeqCst), threads);
+
+        Ok(())
+    }
+}
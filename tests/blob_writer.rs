@@ -5,7 +5,7 @@ use std::sync::Arc;
 use anyhow::{bail, Error};
 use lazy_static::lazy_static;
 
-use pbs_datastore::{DataBlob, DataBlobReader, DataBlobWriter};
+use pbs_datastore::{AsyncDataBlobReader, DataBlob, DataBlobReader, DataBlobWriter};
 use pbs_tools::crypt_config::CryptConfig;
 
 lazy_static! {
@@ -38,7 +38,7 @@ fn verify_test_blob(mut cursor: Cursor<Vec<u8>>, digest: &[u8; 32]) -> Result<()
         println!("Starting DataBlobReader test (size = {})", size);
 
         cursor.seek(SeekFrom::Start(0))?;
-        let mut reader = DataBlobReader::new(&mut cursor, Some(CRYPT_CONFIG.clone()))?;
+        let mut reader = DataBlobReader::new(&mut cursor, Some(CRYPT_CONFIG.clone()), None)?;
         let mut buffer = Vec::<u8>::new();
         // read the whole file
         //reader.read_to_end(&mut buffer)?;
@@ -103,3 +103,191 @@ fn test_encrypted_compressed_blob_writer() -> Result<(), Error> {
 
     verify_test_blob(blob_writer.finish()?, &TEST_DIGEST_ENC)
 }
+
+#[test]
+fn test_uncompressed_blob_seek() -> Result<(), Error> {
+    let tmp = Cursor::new(Vec::<u8>::new());
+    let mut blob_writer = DataBlobWriter::new_uncompressed(tmp)?;
+    blob_writer.write_all(&TEST_DATA)?;
+    let mut cursor = blob_writer.finish()?;
+
+    cursor.seek(SeekFrom::Start(0))?;
+    let mut reader = DataBlobReader::new(&mut cursor, None, None)?;
+    let mut buf = [0u8; 16];
+
+    assert_eq!(reader.seek(SeekFrom::Start(50_000))?, 50_000);
+    reader.read_exact(&mut buf)?;
+    assert_eq!(buf[..], TEST_DATA[50_000..50_016]);
+
+    // backwards seeks are fine for uncompressed blobs
+    assert_eq!(reader.seek(SeekFrom::Current(-10_016))?, 40_000);
+    reader.read_exact(&mut buf)?;
+    assert_eq!(buf[..], TEST_DATA[40_000..40_016]);
+
+    assert_eq!(reader.seek(SeekFrom::End(-16))?, 100_000 - 16);
+    reader.read_exact(&mut buf)?;
+    assert_eq!(buf[..], TEST_DATA[100_000 - 16..]);
+
+    assert!(reader.seek(SeekFrom::Current(-200_000)).is_err());
+
+    // the CRC cannot be verified after skipping data, so finish must not fail
+    reader.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_compressed_blob_forward_seek() -> Result<(), Error> {
+    let tmp = Cursor::new(Vec::<u8>::new());
+    let mut blob_writer = DataBlobWriter::new_compressed(tmp)?;
+    blob_writer.write_all(&TEST_DATA)?;
+    let mut cursor = blob_writer.finish()?;
+
+    cursor.seek(SeekFrom::Start(0))?;
+    let mut reader = DataBlobReader::new(&mut cursor, None, None)?;
+    let mut buf = [0u8; 16];
+
+    assert_eq!(reader.seek(SeekFrom::Start(50_000))?, 50_000);
+    reader.read_exact(&mut buf)?;
+    assert_eq!(buf[..], TEST_DATA[50_000..50_016]);
+
+    assert!(reader.seek(SeekFrom::Start(0)).is_err());
+    assert!(reader.seek(SeekFrom::End(0)).is_err());
+
+    // all data got decoded, so the CRC is still checked
+    std::io::copy(&mut reader, &mut std::io::sink())?;
+    reader.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_async_blob_reader() -> Result<(), Error> {
+    use tokio::io::AsyncReadExt;
+
+    let tmp = Cursor::new(Vec::<u8>::new());
+    let mut blob_writer = DataBlobWriter::new_encrypted_compressed(tmp, CRYPT_CONFIG.clone())?;
+    blob_writer.write_all(&TEST_DATA)?;
+    let raw_data = blob_writer.finish()?.into_inner();
+
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(async move {
+        let mut reader = AsyncDataBlobReader::new(&raw_data[..], Some(CRYPT_CONFIG.clone()));
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+        if data != *TEST_DATA {
+            bail!("blob data is wrong (async reader)");
+        }
+
+        // a corrupted blob must not decode
+        let mut corrupted = raw_data.clone();
+        let len = corrupted.len();
+        corrupted[len - 1] ^= 0xff;
+        let mut reader = AsyncDataBlobReader::new(&corrupted[..], Some(CRYPT_CONFIG.clone()));
+        assert!(reader.read_to_end(&mut Vec::new()).await.is_err());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn test_blob_compression_level() -> Result<(), Error> {
+    // a pseudo random mix of words, so that higher levels have something to gain
+    let words = [
+        "proxmox", "backup", "chunk", "blob", "index", "zstd", "level", "store",
+    ];
+    let mut state = 0x2545_f491_u32;
+    let mut data = Vec::new();
+    while data.len() < 256 * 1024 {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        data.extend_from_slice(words[(state >> 16) as usize % words.len()].as_bytes());
+        data.push(b' ');
+    }
+
+    let fast = DataBlob::encode_with_level(&data, None, true, 1)?;
+    let strong = DataBlob::encode_with_level(&data, None, true, 19)?;
+    assert_ne!(fast.raw_size(), strong.raw_size());
+    assert_eq!(fast.decode(None, None)?, data);
+    assert_eq!(strong.decode(None, None)?, data);
+
+    Ok(())
+}
+
+#[test]
+fn test_blob_reader_sizes() -> Result<(), Error> {
+    let tmp = Cursor::new(Vec::<u8>::new());
+    let mut blob_writer = DataBlobWriter::new_uncompressed(tmp)?;
+    blob_writer.write_all(&TEST_DATA)?;
+    let raw_data = blob_writer.finish()?.into_inner();
+
+    let mut reader = DataBlobReader::new(&raw_data[..], None, None)?;
+    let mut buf = [0u8; 1000];
+    reader.read_exact(&mut buf)?;
+    assert_eq!(reader.decoded_size(), 1000);
+
+    std::io::copy(&mut reader, &mut std::io::sink())?;
+    assert_eq!(reader.decoded_size(), TEST_DATA.len() as u64);
+    assert_eq!(reader.raw_size(), raw_data.len() as u64);
+    reader.finish()?;
+
+    let tmp = Cursor::new(Vec::<u8>::new());
+    let mut blob_writer = DataBlobWriter::new_encrypted_compressed(tmp, CRYPT_CONFIG.clone())?;
+    blob_writer.write_all(&TEST_DATA)?;
+    let raw_data = blob_writer.finish()?.into_inner();
+
+    let mut reader = DataBlobReader::new(&raw_data[..], Some(CRYPT_CONFIG.clone()), None)?;
+    std::io::copy(&mut reader, &mut std::io::sink())?;
+    assert_eq!(reader.decoded_size(), TEST_DATA.len() as u64);
+    assert_eq!(reader.raw_size(), raw_data.len() as u64);
+    reader.finish()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_blob_reader_buffer_size() -> Result<(), Error> {
+    let data: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+
+    for kind in 0..4 {
+        let tmp = Cursor::new(Vec::<u8>::new());
+        let raw_data = match kind {
+            0 => {
+                let mut writer = DataBlobWriter::new_uncompressed(tmp)?;
+                writer.write_all(&data)?;
+                writer.finish()?
+            }
+            1 => {
+                let mut writer = DataBlobWriter::new_compressed(tmp)?;
+                writer.write_all(&data)?;
+                writer.finish()?
+            }
+            2 => {
+                let mut writer = DataBlobWriter::new_encrypted(tmp, CRYPT_CONFIG.clone())?;
+                writer.write_all(&data)?;
+                writer.finish()?
+            }
+            _ => {
+                let mut writer =
+                    DataBlobWriter::new_encrypted_compressed(tmp, CRYPT_CONFIG.clone())?;
+                writer.write_all(&data)?;
+                writer.finish()?
+            }
+        }
+        .into_inner();
+
+        for buffer_size in [4096, 1024 * 1024] {
+            let mut reader =
+                DataBlobReader::new(&raw_data[..], Some(CRYPT_CONFIG.clone()), Some(buffer_size))?;
+            let mut decoded = Vec::new();
+            reader.read_to_end(&mut decoded)?;
+            reader.finish()?;
+            if decoded != data {
+                bail!("blob data is wrong (kind {kind}, buffer size {buffer_size})");
+            }
+        }
+    }
+
+    assert!(DataBlobReader::new(&[0u8; 12][..], None, Some(0)).is_err());
+
+    Ok(())
+}
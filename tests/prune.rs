@@ -4,7 +4,7 @@ use anyhow::Error;
 
 use pbs_api_types::PruneJobOptions;
 use pbs_datastore::manifest::MANIFEST_BLOB_NAME;
-use pbs_datastore::prune::compute_prune_info;
+use pbs_datastore::prune::{compute_prune_info, compute_prune_info_with_verify_state};
 use pbs_datastore::{BackupDir, BackupInfo};
 
 fn get_prune_list(
@@ -335,3 +335,71 @@ fn test_prune_simple() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_prune_keep_last_verified() -> Result<(), Error> {
+    let orig_list = vec![
+        create_info("host/elsa/2019-12-01T10:00:00Z", false),
+        create_info("host/elsa/2019-12-02T10:00:00Z", false),
+        create_info("host/elsa/2019-12-03T10:00:00Z", false),
+        // latest snapshot, but its verification failed
+        create_info("host/elsa/2019-12-04T10:00:00Z", false),
+    ];
+    let verified = [
+        PathBuf::from("host/elsa/2019-12-01T10:00:00Z"),
+        PathBuf::from("host/elsa/2019-12-02T10:00:00Z"),
+    ];
+
+    let get_remove_list = |options: &PruneJobOptions| -> Vec<PathBuf> {
+        let mut prune_info =
+            compute_prune_info_with_verify_state(orig_list.clone(), &options.keep, |info| {
+                verified.contains(&info.backup_dir.relative_path())
+            })
+            .unwrap();
+        prune_info.reverse();
+        prune_info
+            .iter()
+            .filter(|(_, mark)| !mark.keep())
+            .map(|(info, _)| info.backup_dir.relative_path())
+            .collect()
+    };
+
+    // the failed latest snapshot does not count, the last verified one is kept in addition
+    let mut options = PruneJobOptions::default();
+    options.keep.keep_last = Some(1);
+    options.keep.keep_last_verified = Some(1);
+    let expect: Vec<PathBuf> = vec![
+        PathBuf::from("host/elsa/2019-12-01T10:00:00Z"),
+        PathBuf::from("host/elsa/2019-12-03T10:00:00Z"),
+    ];
+    assert_eq!(get_remove_list(&options), expect);
+
+    // only verified snapshots are kept
+    let mut options = PruneJobOptions::default();
+    options.keep.keep_last_verified = Some(2);
+    let expect: Vec<PathBuf> = vec![
+        PathBuf::from("host/elsa/2019-12-03T10:00:00Z"),
+        PathBuf::from("host/elsa/2019-12-04T10:00:00Z"),
+    ];
+    assert_eq!(get_remove_list(&options), expect);
+
+    // already kept by keep-last, nothing more to keep
+    let mut options = PruneJobOptions::default();
+    options.keep.keep_last = Some(3);
+    options.keep.keep_last_verified = Some(1);
+    let expect: Vec<PathBuf> = vec![PathBuf::from("host/elsa/2019-12-01T10:00:00Z")];
+    assert_eq!(get_remove_list(&options), expect);
+
+    // unset, so the behavior does not change
+    let mut options = PruneJobOptions::default();
+    options.keep.keep_last = Some(1);
+    let expect: Vec<PathBuf> = vec![
+        PathBuf::from("host/elsa/2019-12-01T10:00:00Z"),
+        PathBuf::from("host/elsa/2019-12-02T10:00:00Z"),
+        PathBuf::from("host/elsa/2019-12-03T10:00:00Z"),
+    ];
+    assert_eq!(get_remove_list(&options), expect);
+    assert_eq!(get_prune_list(orig_list, false, &options), expect);
+
+    Ok(())
+}
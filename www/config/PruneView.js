@@ -3,6 +3,7 @@ Ext.define('pbs-prune-jobs-status', {
     fields: [
 	'id', 'disable', 'store', 'ns', 'max-depth', 'schedule',
 	'keep-last', 'keep-hourly', 'keep-daily', 'keep-weekly', 'keep-monthly', 'keep-yearly',
+	'keep-last-verified',
 	'next-run', 'last-run-upid', 'last-run-state', 'last-run-endtime',
 	{
 	    name: 'duration',
@@ -291,13 +291,16 @@ Ext.define('PBS.DataStoreContent', {
 			group.files = item.files;
 			group.size = item.size;
 			group.owner = item.owner;
-			verify.lastFailed = item.verification && item.verification.state !== 'ok';
+			verify.lastFailed = item.verification && item.verification.state !== 'ok' &&
+			    !item.verification.aborted;
 		    }
 		    if (!item.verification) {
 			verify.none++;
 		    } else {
 			if (item.verification.state === 'ok') {
 			    verify.ok++;
+			} else if (item.verification.aborted) {
+			    verify.none++;
 			} else {
 			    verify.failed++;
 			}
@@ -1199,6 +1202,8 @@ Ext.define('PBS.DataStoreContent', {
 			    tip = `Last verify task over 30 days ago: ${verify_time}`;
 			    iconCls = 'check warning';
 			}
+		    } else if (v.aborted) {
+			iconCls = 'exclamation-triangle warning';
 		    }
 		}
 		return `<span data-qtip="${tip}">
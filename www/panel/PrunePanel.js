@@ -59,6 +59,14 @@ Ext.define('PBS.panel.PruneInputPanel', {
 		deleteEmpty: '{!isCreate}',
 	    },
 	},
+	{
+	    xtype: 'pbsPruneKeepInput',
+	    name: 'keep-last-verified',
+	    fieldLabel: gettext('Keep Last Verified'),
+	    cbind: {
+		deleteEmpty: '{!isCreate}',
+	    },
+	},
 	{
 	    xtype: 'proxmoxcheckbox',
 	    name: 'dry-run',